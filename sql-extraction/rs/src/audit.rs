@@ -0,0 +1,174 @@
+use crate::dialect::Dialect;
+use crate::injection::is_conversion;
+use crate::{span_range, visit_source, Config, Range};
+use serde::Serialize;
+use syn::spanned::Spanned;
+
+/// What a non-literal SQL argument is.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DynamicKind {
+    LocalVariable,
+    ConstPath,
+    Format,
+    FunctionResult,
+    Unknown,
+}
+
+/// A configured call, with its SQL when it is a literal.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct CallSiteReport {
+    pub function: String, // ex) "sqlx::query"
+    pub range: Range,
+    /// range of the SQL argument
+    pub arg_range: Range,
+    /// the literal content, as in `SqlNode`
+    pub content: Option<String>,
+    pub dynamic: Option<DynamicKind>,
+}
+
+/// Every configured call of a file in source order, including those whose SQL cannot be extracted.
+pub fn report_call_sites(
+    source_txt: &str,
+    configs: &[Config],
+    default_dialect: Dialect,
+) -> Result<Vec<CallSiteReport>, syn::Error> {
+    let visitor = visit_source(source_txt, configs, default_dialect)?;
+    let literals = visitor
+        .sql_node_list
+        .into_iter()
+        .map(|sql_node| CallSiteReport {
+            function: sql_node.call.path.join("::"),
+            range: sql_node.call.range,
+            arg_range: sql_node.literal.range,
+            content: Some(sql_node.content),
+            dynamic: None,
+        });
+    let dynamics = visitor
+        .dynamic_sql_list
+        .into_iter()
+        .map(|dynamic_sql| CallSiteReport {
+            function: dynamic_sql.call.path.join("::"),
+            range: dynamic_sql.call.range,
+            arg_range: dynamic_sql
+                .call
+                .args
+                .get(dynamic_sql.call.sql_arg_no)
+                .map(|arg| arg.range)
+                .unwrap_or_else(|| span_range(dynamic_sql.expr.span())),
+            content: None,
+            dynamic: Some(classify(&dynamic_sql.expr)),
+        });
+
+    let mut reports: Vec<CallSiteReport> = literals.chain(dynamics).collect();
+    reports.sort_by_key(|r| r.arg_range.start);
+    Ok(reports)
+}
+
+pub fn classify(expr: &syn::Expr) -> DynamicKind {
+    match expr {
+        syn::Expr::Reference(reference) => classify(&reference.expr),
+        syn::Expr::Paren(paren) => classify(&paren.expr),
+        syn::Expr::Path(path) => match path.path.get_ident() {
+            Some(ident) if !ident.to_string().chars().any(|c| c.is_ascii_uppercase()) => {
+                DynamicKind::LocalVariable
+            }
+            _ => DynamicKind::ConstPath,
+        },
+        syn::Expr::Macro(expr_macro)
+            if expr_macro
+                .mac
+                .path
+                .segments
+                .last()
+                .is_some_and(|s| s.ident == "format") =>
+        {
+            DynamicKind::Format
+        }
+        // ex) sql.as_str(), format!(...).as_str()
+        syn::Expr::MethodCall(method_call) if is_conversion(method_call) => {
+            classify(&method_call.receiver)
+        }
+        syn::Expr::Call(_) | syn::Expr::MethodCall(_) => DynamicKind::FunctionResult,
+        _ => DynamicKind::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn literal_and_dynamic_call_sites() {
+        let configs = vec![Config {
+            functionName: "query".to_string(),
+            sqlArgNo: 0,
+            isMacro: false,
+            dialect: None,
+        }];
+        let result = report_call_sites(
+            r#"
+const LIST: &str = "SELECT id FROM todos";
+
+async fn list(pool: &PgPool, sql: String) {
+    sqlx::query("SELECT 1");
+    sqlx::query(&sql);
+    sqlx::query(LIST);
+    sqlx::query(queries::LIST);
+    sqlx::query(&format!("SELECT {}", 1));
+    sqlx::query(build_query(pool).as_str());
+    sqlx::query(self.sql);
+}
+"#,
+            &configs,
+            Dialect::default(),
+        )
+        .unwrap();
+        let summary: Vec<(Option<&str>, Option<DynamicKind>)> = result
+            .iter()
+            .map(|r| (r.content.as_deref(), r.dynamic))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("SELECT 1"), None),
+                (None, Some(DynamicKind::LocalVariable)),
+                (None, Some(DynamicKind::ConstPath)),
+                (None, Some(DynamicKind::ConstPath)),
+                (None, Some(DynamicKind::Format)),
+                (None, Some(DynamicKind::FunctionResult)),
+                (None, Some(DynamicKind::Unknown)),
+            ]
+        );
+        assert_eq!(result[1].function, "sqlx::query");
+        // `&sql`
+        assert_eq!(
+            (
+                result[1].arg_range.start.character,
+                result[1].arg_range.end.character
+            ),
+            (16, 20)
+        );
+    }
+
+    #[test]
+    fn macro_with_non_literal_argument() {
+        let result = report_call_sites(
+            r#"
+fn main() {
+    sqlx::query!(include_str!("list.sql"));
+    sqlx::query_as!(Todo, SQL);
+}
+"#,
+            &crate::default_configs(),
+            Dialect::default(),
+        )
+        .unwrap();
+        let dynamic: Vec<Option<DynamicKind>> = result.iter().map(|r| r.dynamic).collect();
+        assert_eq!(
+            dynamic,
+            vec![Some(DynamicKind::Unknown), Some(DynamicKind::ConstPath)]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Command line interface for the native API.
+//!
+//! ```text
+//! sqlsurge pg-stats <stats.csv> [--root <dir>]
+//! sqlsurge sqlite-check [--root <dir>] [--migrations <dir>]
+//! sqlsurge format-check [--root <dir>] [--dialect <dialect>]
+//! sqlsurge config-schema
+//! ```
+//!
+//! Queries are found with the `sqlsurge.toml` or `Cargo.toml` metadata of the workspace,
+//! see `rs::project`.
+
+use rs::dialect::Dialect;
+use rs::{format, pg_stats, project};
+use similar::TextDiff;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage:
+  sqlsurge pg-stats <stats.csv> [--root <dir>]
+  sqlsurge sqlite-check [--root <dir>] [--migrations <dir>]
+  sqlsurge format-check [--root <dir>] [--dialect <dialect>]
+  sqlsurge config-schema";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("pg-stats") => pg_stats_command(&args[1..]),
+        Some("sqlite-check") => sqlite_check_command(&args[1..]),
+        Some("format-check") => format_check_command(&args[1..]),
+        Some("config-schema") if args.len() == 1 => {
+            println!("{}", rs::validation::config_schema());
+            Ok(ExitCode::SUCCESS)
+        }
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Print `file:line function` of the call sites of each row of a `pg_stat_statements` export.
+fn pg_stats_command(args: &[String]) -> Result<ExitCode, String> {
+    let mut csv_path = None;
+    let mut root = PathBuf::from(".");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(args.next().ok_or(USAGE)?),
+            _ if csv_path.is_none() => csv_path = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let csv_path = csv_path.ok_or(USAGE)?;
+
+    let csv_txt =
+        std::fs::read_to_string(&csv_path).map_err(|e| format!("{}: {}", csv_path.display(), e))?;
+    let rows =
+        pg_stats::read_stats_csv(&csv_txt).map_err(|e| format!("{}: {}", csv_path.display(), e))?;
+    let settings = project::discover(&root)?;
+    let matches =
+        pg_stats::join_stats_in_workspace(rows, &root, &settings.configs, Dialect::PostgreSql)
+            .map_err(|e| format!("{}: {}", root.display(), e))?;
+
+    for stats_match in matches {
+        println!(
+            "{}",
+            stats_match
+                .row
+                .query
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        if stats_match.call_sites.is_empty() {
+            println!("  (no call site found)");
+        }
+        for call_site in stats_match.call_sites {
+            println!(
+                "  {}:{} {}",
+                call_site.path,
+                call_site.line,
+                call_site.function.as_deref().unwrap_or("-")
+            );
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prepare the SQLite queries of the workspace against the migrations, exit with 1 on errors.
+#[cfg(feature = "sqlite")]
+fn sqlite_check_command(args: &[String]) -> Result<ExitCode, String> {
+    let mut root = PathBuf::from(".");
+    let mut migrations_dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(args.next().ok_or(USAGE)?),
+            "--migrations" => migrations_dir = Some(PathBuf::from(args.next().ok_or(USAGE)?)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let settings = rs::project::discover(&root)?;
+    let migrations_dir = migrations_dir.unwrap_or(settings.migrations);
+
+    let diagnostics = rs::sqlite::check_workspace(&root, &migrations_dir, &settings.configs)
+        .map_err(|e| format!("{}: {}", root.display(), e))?;
+    for file_diagnostic in &diagnostics {
+        let diagnostic = &file_diagnostic.diagnostic;
+        // 1-indexed like compiler output
+        println!(
+            "{}:{}:{}: error[{}] {}",
+            file_diagnostic.path,
+            diagnostic.range.start.line + 1,
+            diagnostic.range.start.character + 1,
+            diagnostic.code,
+            diagnostic.message
+        );
+    }
+    Ok(if diagnostics.is_empty() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn sqlite_check_command(_args: &[String]) -> Result<ExitCode, String> {
+    Err("sqlsurge was built without the `sqlite` feature".to_string())
+}
+
+/// Print a diff of each literal of the workspace whose query is not formatted with the
+/// `format` options of its crate, exit with 1 if there is one. Nothing is written.
+fn format_check_command(args: &[String]) -> Result<ExitCode, String> {
+    let mut root = PathBuf::from(".");
+    let mut dialect = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--root" => root = PathBuf::from(args.next().ok_or(USAGE)?),
+            "--dialect" => dialect = Some(args.next().ok_or(USAGE)?.parse()?),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+
+    // each crate of a workspace with its own settings
+    let unformatted: Vec<format::Unformatted> = project::read_workspace(&root)?
+        .iter()
+        .flat_map(|(source, settings)| {
+            format::check_formatting(
+                std::slice::from_ref(source),
+                &settings.configs,
+                dialect.unwrap_or(settings.dialect),
+                &settings.format,
+            )
+        })
+        .collect();
+    for literal in &unformatted {
+        let location = format!(
+            "{}:{}:{}",
+            literal.path,
+            literal.range.start.line + 1,
+            literal.range.start.character + 1
+        );
+        print!(
+            "{}",
+            TextDiff::from_lines(&literal.current, &literal.formatted)
+                .unified_diff()
+                .missing_newline_hint(false)
+                .header(&location, &format!("{} (formatted)", location))
+        );
+    }
+    if unformatted.is_empty() {
+        return Ok(ExitCode::SUCCESS);
+    }
+    eprintln!("{} queries are not formatted", unformatted.len());
+    Ok(ExitCode::FAILURE)
+}
@@ -0,0 +1,184 @@
+use crate::edit::{text_of, TextEdit};
+use crate::placeholder::bind_chain;
+use crate::{span_range, Range, SqlNode};
+use syn::spanned::Spanned;
+
+// runtime functions and the macros that check the same query at compile time
+const MACROS: [(&str, &str); 6] = [
+    ("query", "query"),
+    ("query_as", "query_as"),
+    ("query_scalar", "query_scalar"),
+    ("query", "query_unchecked"),
+    ("query_as", "query_as_unchecked"),
+    ("query_scalar", "query_scalar_unchecked"),
+];
+
+/// Edits converting the call of a node between the runtime functions and the sqlx macros:
+/// `sqlx::query_as::<_, T>("...").bind(a).bind(b)` into `sqlx::query_as!(T, "...", a, b)`,
+/// and back. The literal is kept as written. `None` if the call has no counterpart, or if
+/// the record type of a runtime `query_as` is inferred and cannot be named in the macro.
+pub fn convert_query_call(source_txt: &str, sql_node: &SqlNode) -> Option<Vec<TextEdit>> {
+    let call = &sql_node.call;
+    let literal = text_of(source_txt, &sql_node.literal.range);
+    let module: Vec<&str> = call.path[..call.path.len() - 1]
+        .iter()
+        .map(String::as_str)
+        .collect();
+    let qualified = |name: &str| {
+        module
+            .iter()
+            .copied()
+            .chain(std::iter::once(name))
+            .collect::<Vec<_>>()
+            .join("::")
+    };
+
+    if call.is_macro {
+        let (function, _) = MACROS
+            .iter()
+            .find(|(_, mac)| *mac == call.function_name())?;
+        let sql_arg_no = call.sql_arg_no;
+        let generics = match *function {
+            "query_as" => format!("::<_, {}>", text_of(source_txt, &call.args.first()?.range)),
+            _ => String::new(),
+        };
+        // one `.bind()` per line, one level deeper than the call
+        let indent: String = source_txt
+            .split('\n')
+            .nth(call.range.start.line)
+            .unwrap_or_default()
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let binds: String = call.args[sql_arg_no + 1..]
+            .iter()
+            .map(|arg| format!("\n{}    .bind({})", indent, text_of(source_txt, &arg.range)))
+            .collect();
+        return Some(vec![TextEdit {
+            range: call.range,
+            new_text: format!("{}{}({}){}", qualified(function), generics, literal, binds),
+        }]);
+    }
+
+    let (_, mac) = MACROS
+        .iter()
+        .find(|(function, _)| *function == call.function_name())?;
+    let record_type = match *mac {
+        "query_as" => Some(turbofish_type(&text_of(source_txt, &call.path_range))?),
+        _ => None,
+    };
+    let chain = bind_chain(source_txt, call)?;
+    let args: Vec<String> = record_type
+        .into_iter()
+        .chain(std::iter::once(literal))
+        .chain(
+            chain
+                .iter()
+                .map(|bind| text_of(source_txt, &bind.arg_range)),
+        )
+        .collect();
+    Some(vec![TextEdit {
+        range: Range {
+            start: call.range.start,
+            end: chain.last().map_or(call.range.end, |bind| bind.range.end),
+        },
+        new_text: format!("{}!({})", qualified(mac), args.join(", ")),
+    }])
+}
+
+// `T` of `sqlx::query_as::<_, T>`, `None` when it is inferred
+fn turbofish_type(path_txt: &str) -> Option<String> {
+    let path: syn::ExprPath = syn::parse_str(path_txt).ok()?;
+    match &path.path.segments.last()?.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.last()? {
+            syn::GenericArgument::Type(syn::Type::Infer(_)) => None,
+            // as written, ex) `models::Todo`
+            syn::GenericArgument::Type(ty) => Some(text_of(path_txt, &span_range(ty.span()))),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{default_configs, extract_sql_nodes, Config};
+    use pretty_assertions::assert_eq;
+
+    fn runtime_configs() -> Vec<Config> {
+        ["query", "query_as"]
+            .iter()
+            .map(|name| Config {
+                functionName: name.to_string(),
+                sqlArgNo: 0,
+                isMacro: false,
+                dialect: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn runtime_to_macro() {
+        let source_txt = r#"
+async fn find(pool: &PgPool, id: i64, done: bool) {
+    let todo = sqlx::query_as::<_, models::Todo>("SELECT id FROM todos WHERE id = $1 AND done = $2")
+        .bind(id)
+        .bind(done)
+        .fetch_one(pool)
+        .await?;
+}
+"#;
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &runtime_configs(), Dialect::PostgreSql).unwrap();
+        let edits = convert_query_call(source_txt, &sql_nodes[0]).unwrap();
+        assert_eq!(
+            edits[0].new_text,
+            "sqlx::query_as!(models::Todo, \"SELECT id FROM todos WHERE id = $1 AND done = $2\", id, done)"
+        );
+        assert_eq!(edits[0].range.start, sql_nodes[0].call.range.start);
+        // up to the last `.bind()`, `.fetch_one(pool)` is kept
+        assert_eq!(edits[0].range.end.line, 4);
+        assert_eq!(edits[0].range.end.character, 19);
+
+        // the record type is inferred
+        let source_txt = r#"fn f() { let t: Todo = sqlx::query_as("SELECT 1").fetch_one(pool); }"#;
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &runtime_configs(), Dialect::PostgreSql).unwrap();
+        assert_eq!(convert_query_call(source_txt, &sql_nodes[0]), None);
+
+        // a `.bind()` after another method would be left on the macro
+        let source_txt = r#"
+async fn find(pool: &PgPool, id: i64) {
+    sqlx::query("SELECT id FROM todos WHERE id = $1")
+        .persistent(false)
+        .bind(id)
+        .execute(pool)
+        .await?;
+}
+"#;
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &runtime_configs(), Dialect::PostgreSql).unwrap();
+        assert_eq!(convert_query_call(source_txt, &sql_nodes[0]), None);
+    }
+
+    #[test]
+    fn macro_to_runtime() {
+        let source_txt = r#"
+async fn find(pool: &PgPool, id: i64) {
+    let todo = sqlx::query_as!(Todo, r"SELECT id FROM todos WHERE id = $1", id)
+        .fetch_one(pool)
+        .await?;
+}
+"#;
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql).unwrap();
+        let edits = convert_query_call(source_txt, &sql_nodes[0]).unwrap();
+        assert_eq!(edits[0].range, sql_nodes[0].call.range);
+        assert_eq!(
+            edits[0].new_text,
+            "sqlx::query_as::<_, Todo>(r\"SELECT id FROM todos WHERE id = $1\")\n        .bind(id)"
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use crate::dialect::Dialect;
+use crate::references::{self, ReferenceKind};
+use crate::schema::migrations::Migration;
+use crate::schema::same_name;
+use crate::syntax::parse_each_statement;
+use crate::workspace::Location;
+use crate::{Position, Range, SqlNode};
+use serde::Serialize;
+use sqlparser::ast::{AlterTableOperation, ObjectName, Statement};
+
+/// Where a table or column of a query is defined in the migrations.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Definition {
+    pub table: String,
+    pub column: Option<String>,
+    /// the `CREATE TABLE`, or the `ALTER TABLE ... ADD COLUMN` of a column
+    pub definition: Location,
+    /// later `ALTER TABLE` statements that changed it, in the order they are applied
+    pub alterations: Vec<Location>,
+}
+
+/// Resolve the identifier at `position` and find its definition in `migrations` (in the order they are applied).
+pub fn find_definition(
+    sql_node: &SqlNode,
+    position: &Position,
+    migrations: &[Migration],
+    dialect: Dialect,
+) -> Option<Definition> {
+    let reference = references::resolve(sql_node)
+        .into_iter()
+        .find(|r| r.range.contains(position))?;
+    let (table, column) = match reference.kind {
+        ReferenceKind::Table => (reference.name, None),
+        ReferenceKind::Alias => (reference.table?, None),
+        ReferenceKind::Column => (reference.table?, Some(reference.name)),
+        ReferenceKind::Cte | ReferenceKind::Function => return None,
+    };
+
+    let statements: Vec<(&str, Statement, Range)> = migrations
+        .iter()
+        .flat_map(|migration| {
+            parse_each_statement(&migration.sql, dialect)
+                .into_iter()
+                .filter_map(|parsed| {
+                    Some((
+                        migration.path.as_str(),
+                        parsed.statement.ok()?,
+                        parsed.range,
+                    ))
+                })
+        })
+        .collect();
+
+    // walk back from the latest migration, following renames to the original names
+    let (mut current_table, mut current_column) = (table.clone(), column.clone());
+    let mut alterations = Vec::new();
+    for (path, statement, range) in statements.iter().rev() {
+        let location = Location {
+            path: path.to_string(),
+            range: *range,
+        };
+        match statement {
+            Statement::CreateTable(create_table)
+                if is_named(&create_table.name, &current_table) =>
+            {
+                let defines = match &current_column {
+                    None => true,
+                    Some(column) => create_table
+                        .columns
+                        .iter()
+                        .any(|c| same_name(&c.name.value, column)),
+                };
+                if !defines {
+                    return None;
+                }
+                alterations.reverse();
+                return Some(Definition {
+                    table,
+                    column,
+                    definition: location,
+                    alterations,
+                });
+            }
+            Statement::AlterTable {
+                name, operations, ..
+            } => {
+                if let Some(AlterTableOperation::RenameTable { table_name }) = operations.first() {
+                    if is_named(table_name, &current_table) {
+                        if current_column.is_none() {
+                            alterations.push(location);
+                        }
+                        current_table = name.0.last()?.value.clone();
+                        continue;
+                    }
+                }
+                if !is_named(name, &current_table) {
+                    continue;
+                }
+                let current_column = match &mut current_column {
+                    None => {
+                        alterations.push(location);
+                        continue;
+                    }
+                    Some(column) => column,
+                };
+                for operation in operations {
+                    match operation {
+                        AlterTableOperation::AddColumn { column_def, .. }
+                            if same_name(&column_def.name.value, current_column) =>
+                        {
+                            alterations.reverse();
+                            return Some(Definition {
+                                table,
+                                column,
+                                definition: location,
+                                alterations,
+                            });
+                        }
+                        AlterTableOperation::RenameColumn {
+                            old_column_name,
+                            new_column_name,
+                        } if same_name(&new_column_name.value, current_column) => {
+                            alterations.push(location.clone());
+                            *current_column = old_column_name.value.clone();
+                        }
+                        AlterTableOperation::ChangeColumn {
+                            old_name, new_name, ..
+                        } if same_name(&new_name.value, current_column) => {
+                            alterations.push(location.clone());
+                            *current_column = old_name.value.clone();
+                        }
+                        AlterTableOperation::AlterColumn { column_name, .. }
+                        | AlterTableOperation::ModifyColumn {
+                            col_name: column_name,
+                            ..
+                        } if same_name(&column_name.value, current_column) => {
+                            alterations.push(location.clone())
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_named(name: &ObjectName, table: &str) -> bool {
+    name.0.last().is_some_and(|i| same_name(&i.value, table))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{default_configs, extract_sql_nodes};
+    use pretty_assertions::assert_eq;
+
+    fn migration(path: &str, sql: &str) -> Migration {
+        Migration {
+            path: path.to_string(),
+            sql: sql.to_string(),
+        }
+    }
+
+    fn definition_at(position: Position, migrations: &[Migration]) -> Option<Definition> {
+        let sql_nodes = extract_sql_nodes(
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT done FROM todos WHERE title <> ''");
+}
+"#,
+            &default_configs(),
+            Dialect::PostgreSql,
+        )
+        .unwrap();
+        find_definition(&sql_nodes[0], &position, migrations, Dialect::PostgreSql)
+    }
+
+    fn range(start: (usize, usize), end: (usize, usize)) -> Range {
+        Range {
+            start: Position {
+                line: start.0,
+                character: start.1,
+            },
+            end: Position {
+                line: end.0,
+                character: end.1,
+            },
+        }
+    }
+
+    #[test]
+    fn table_and_column_definitions() {
+        let migrations = vec![
+            migration(
+                "migrations/1_todos.sql",
+                "CREATE TABLE tasks (\n  id BIGSERIAL PRIMARY KEY,\n  description TEXT,\n  done BOOLEAN\n);\n",
+            ),
+            migration(
+                "migrations/2_rename.sql",
+                "ALTER TABLE tasks RENAME TO todos;\nALTER TABLE todos RENAME COLUMN description TO title;\n",
+            ),
+            migration(
+                "migrations/3_done.sql",
+                "ALTER TABLE todos ALTER COLUMN done SET NOT NULL;",
+            ),
+        ];
+
+        // `todos`
+        let result = definition_at(
+            Position {
+                line: 2,
+                character: 35,
+            },
+            &migrations,
+        )
+        .unwrap();
+        assert_eq!(
+            result.definition,
+            Location {
+                path: "migrations/1_todos.sql".to_string(),
+                range: range((0, 0), (4, 1)),
+            }
+        );
+        assert_eq!(
+            result.alterations,
+            vec![
+                Location {
+                    path: "migrations/2_rename.sql".to_string(),
+                    range: range((0, 0), (0, 33)),
+                },
+                Location {
+                    path: "migrations/2_rename.sql".to_string(),
+                    range: range((1, 0), (1, 52)),
+                },
+                Location {
+                    path: "migrations/3_done.sql".to_string(),
+                    range: range((0, 0), (0, 48)),
+                },
+            ]
+        );
+
+        // `done`
+        let result = definition_at(
+            Position {
+                line: 2,
+                character: 25,
+            },
+            &migrations,
+        )
+        .unwrap();
+        assert_eq!(result.column.as_deref(), Some("done"));
+        assert_eq!(result.definition.path, "migrations/1_todos.sql");
+        assert_eq!(result.alterations.len(), 1);
+        assert_eq!(result.alterations[0].path, "migrations/3_done.sql");
+
+        // `title` was renamed from `description`
+        let result = definition_at(
+            Position {
+                line: 2,
+                character: 47,
+            },
+            &migrations,
+        )
+        .unwrap();
+        assert_eq!(result.definition.path, "migrations/1_todos.sql");
+        assert_eq!(
+            result.alterations,
+            vec![Location {
+                path: "migrations/2_rename.sql".to_string(),
+                range: range((1, 0), (1, 52)),
+            }]
+        );
+    }
+
+    #[test]
+    fn column_added_by_alter() {
+        let migrations = vec![
+            migration(
+                "migrations/1_todos.sql",
+                "CREATE TABLE todos (id BIGSERIAL);",
+            ),
+            migration(
+                "migrations/2_done.sql",
+                "ALTER TABLE todos ADD COLUMN done BOOLEAN, ADD COLUMN title TEXT;",
+            ),
+        ];
+        let result = definition_at(
+            Position {
+                line: 2,
+                character: 25,
+            },
+            &migrations,
+        )
+        .unwrap();
+        assert_eq!(result.definition.path, "migrations/2_done.sql");
+        assert_eq!(result.alterations, vec![]);
+
+        // not in a query
+        assert_eq!(definition_at(Position::default(), &migrations), None);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::Range;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// A problem found in embedded SQL, located in the Rust source.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub range: Range,
+    pub severity: Severity,
+    pub code: String, // ex) "syntax-error"
+    pub message: String,
+}
+
+pub type SerializedDiagnosticList = Vec<String>;
+
+/// A diagnostic in a file of the workspace, ex) from a workspace-wide check.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileDiagnostic {
+    pub path: String,
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+}
@@ -0,0 +1,222 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// SQL dialect used to parse and analyze embedded queries.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Dialect {
+    #[default]
+    Generic,
+    PostgreSql,
+    MySql,
+    Sqlite,
+}
+
+impl Dialect {
+    pub fn parser_dialect(&self) -> Box<dyn sqlparser::dialect::Dialect> {
+        match self {
+            Dialect::Generic => Box::new(sqlparser::dialect::GenericDialect {}),
+            Dialect::PostgreSql => Box::new(sqlparser::dialect::PostgreSqlDialect {}),
+            Dialect::MySql => Box::new(sqlparser::dialect::MySqlDialect {}),
+            Dialect::Sqlite => Box::new(sqlparser::dialect::SQLiteDialect {}),
+        }
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "generic" => Ok(Dialect::Generic),
+            "postgresql" | "postgres" | "pg" => Ok(Dialect::PostgreSql),
+            "mysql" | "mariadb" => Ok(Dialect::MySql),
+            "sqlite" => Ok(Dialect::Sqlite),
+            _ => Err(format!(
+                "unknown dialect '{}', expected one of generic, postgresql, mysql, sqlite",
+                s
+            )),
+        }
+    }
+}
+
+/// Infer a dialect from a type, ex) `&PgPool`, `Pool<Sqlite>`, `rusqlite::Connection`.
+pub(crate) fn infer_from_type(ty: &syn::Type) -> Option<Dialect> {
+    let idents = idents(ty.to_token_stream());
+    idents.iter().find_map(|ident| match ident.as_str() {
+        // sqlx, diesel, postgres and tokio-postgres
+        "PgPool" | "PgConnection" | "PgPoolOptions" | "PgExecutor" | "PgTransaction"
+        | "Postgres" | "postgres" | "tokio_postgres" => Some(Dialect::PostgreSql),
+        // sqlx, diesel, mysql and mysql_async
+        "MySqlPool" | "MySqlConnection" | "MySqlPoolOptions" | "MySqlExecutor"
+        | "MySqlTransaction" | "MySql" | "MysqlConnection" | "mysql" | "mysql_async" => {
+            Some(Dialect::MySql)
+        }
+        // sqlx, diesel and rusqlite
+        "SqlitePool" | "SqliteConnection" | "SqlitePoolOptions" | "SqliteExecutor"
+        | "SqliteTransaction" | "Sqlite" | "rusqlite" => Some(Dialect::Sqlite),
+        _ => None,
+    })
+}
+
+/// Infer a dialect from a sea-orm backend argument, ex) `DbBackend::Postgres`.
+pub(crate) fn infer_from_expr(expr: &syn::Expr) -> Option<Dialect> {
+    let path = match expr {
+        syn::Expr::Path(syn::ExprPath { path, .. }) => path,
+        _ => return None,
+    };
+    let segments: Vec<String> = path.segments.iter().map(|s| s.ident.to_string()).collect();
+    match segments.as_slice() {
+        [.., backend, variant] if backend == "DbBackend" || backend == "DatabaseBackend" => {
+            match variant.as_str() {
+                "Postgres" => Some(Dialect::PostgreSql),
+                "MySql" => Some(Dialect::MySql),
+                "Sqlite" => Some(Dialect::Sqlite),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn idents(tokens: TokenStream) -> Vec<String> {
+    tokens
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Ident(ident) => vec![ident.to_string()],
+            TokenTree::Group(group) => idents(group.stream()),
+            _ => vec![],
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{extract_sql_nodes, Config};
+    use pretty_assertions::assert_eq;
+
+    fn dialects(source_txt: &str, configs: &[Config]) -> Vec<Dialect> {
+        extract_sql_nodes(source_txt, configs, Dialect::Generic)
+            .unwrap()
+            .iter()
+            .map(|sql_node| sql_node.dialect)
+            .collect()
+    }
+
+    #[test]
+    fn infer_dialect_from_fn_params_and_locals() {
+        let result = dialects(
+            r#"
+async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
+    sqlx::query!("INSERT INTO todos ( description ) VALUES ( $1 )", description);
+}
+
+async fn count(pool: &Pool<Sqlite>) {
+    sqlx::query!("SELECT COUNT(*) FROM todos");
+}
+
+fn open() {
+    sqlx::query!("SELECT 1");
+    let conn: rusqlite::Connection = rusqlite::Connection::open_in_memory().unwrap();
+    sqlx::query!("SELECT 2");
+}
+
+impl Repo {
+    fn run(&self, conn: &mut MySqlConnection) {
+        let f = |conn: &mut SqliteConnection| sqlx::query!("SELECT 3");
+        sqlx::query!("SELECT 4");
+    }
+}
+            "#,
+            &crate::default_configs(),
+        );
+        assert_eq!(
+            result,
+            vec![
+                Dialect::PostgreSql,
+                Dialect::Sqlite,
+                Dialect::Generic,
+                Dialect::Sqlite,
+                Dialect::Sqlite,
+                Dialect::MySql,
+            ]
+        );
+    }
+
+    #[test]
+    fn local_dialect_ends_with_its_block() {
+        let result = dialects(
+            r#"
+const _: () = {
+    let conn: rusqlite::Connection = rusqlite::Connection::open_in_memory().unwrap();
+    sqlx::query!("SELECT 1");
+};
+
+fn later() {
+    {
+        let conn: rusqlite::Connection = rusqlite::Connection::open_in_memory().unwrap();
+        sqlx::query!("SELECT 2");
+    }
+    sqlx::query!("SELECT 3");
+}
+            "#,
+            &crate::default_configs(),
+        );
+        assert_eq!(
+            result,
+            vec![Dialect::Sqlite, Dialect::Sqlite, Dialect::Generic]
+        );
+    }
+
+    #[test]
+    fn infer_dialect_from_backend_argument_and_config() {
+        let configs = vec![
+            Config {
+                functionName: "from_string".to_string(),
+                sqlArgNo: 1,
+                isMacro: false,
+                dialect: None,
+            },
+            Config {
+                functionName: "sql_query".to_string(),
+                sqlArgNo: 0,
+                isMacro: false,
+                dialect: Some(Dialect::Sqlite),
+            },
+        ];
+        let result = dialects(
+            r#"
+fn main() {
+    let stmt = Statement::from_string(DbBackend::Postgres, "SELECT id FROM todos");
+    let stmt = Statement::from_string(sea_orm::DatabaseBackend::MySql, "SELECT id FROM todos");
+    let results = diesel::sql_query("SELECT id FROM todos");
+}
+
+fn with_conn(conn: &mut PgConnection) {
+    let results = diesel::sql_query("SELECT id FROM todos");
+}
+            "#,
+            &configs,
+        );
+        assert_eq!(
+            result,
+            vec![
+                Dialect::PostgreSql,
+                Dialect::MySql,
+                Dialect::Sqlite,
+                Dialect::PostgreSql,
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dialect_names() {
+        assert_eq!("postgres".parse::<Dialect>(), Ok(Dialect::PostgreSql));
+        assert_eq!("SQLite".parse::<Dialect>(), Ok(Dialect::Sqlite));
+        assert!("oracle".parse::<Dialect>().is_err());
+    }
+}
@@ -0,0 +1,43 @@
+use crate::Range;
+use serde::Serialize;
+
+/// Replace `range` of a Rust file with `new_text`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TextEdit {
+    pub range: Range,
+    pub new_text: String,
+}
+
+/// Edits of one file, sorted by position and not overlapping.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FileEdit {
+    pub path: String,
+    pub edits: Vec<TextEdit>,
+}
+
+/// Text of `range` in a source file.
+pub fn text_of(source_txt: &str, range: &Range) -> String {
+    source_txt
+        .split('\n')
+        .enumerate()
+        .skip(range.start.line)
+        .take(range.end.line + 1 - range.start.line)
+        .map(|(i, line)| {
+            let chars = line.chars();
+            let start = if i == range.start.line {
+                range.start.character
+            } else {
+                0
+            };
+            if i == range.end.line {
+                chars
+                    .take(range.end.character)
+                    .skip(start)
+                    .collect::<String>()
+            } else {
+                chars.skip(start).collect::<String>()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,309 @@
+use crate::edit::TextEdit;
+use crate::format::{encode, raw_hashes};
+use crate::literal::LiteralKind;
+use crate::{span_range, Position, Range, SqlNode};
+use serde::Serialize;
+use std::str::FromStr;
+use syn::spanned::Spanned;
+
+/// Where to move the SQL of a call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtractTarget {
+    /// `const NAME: &str = r#"..."#;` above the enclosing item
+    Const,
+    /// `queries/<name>.sql`, read with `query_file!` and friends
+    File,
+}
+
+impl FromStr for ExtractTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "const" => Ok(ExtractTarget::Const),
+            "file" => Ok(ExtractTarget::File),
+            _ => Err(format!(
+                "unknown extract target '{}', expected one of const, file",
+                s
+            )),
+        }
+    }
+}
+
+/// Edits of the source, and the files to create (paths relative to the crate root).
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct QueryExtraction {
+    pub edits: Vec<TextEdit>,
+    pub files: Vec<NewFile>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct NewFile {
+    pub path: String,
+    pub content: String,
+}
+
+// macros that take the SQL inline and their `query_file` counterparts
+const FILE_MACROS: [(&str, &str); 6] = [
+    ("query", "query_file"),
+    ("query_as", "query_file_as"),
+    ("query_scalar", "query_file_scalar"),
+    ("query_unchecked", "query_file_unchecked"),
+    ("query_as_unchecked", "query_file_as_unchecked"),
+    ("query_scalar_unchecked", "query_file_scalar_unchecked"),
+];
+
+/// Move the SQL of a node out of the function body, named after the enclosing function.
+/// The SQL is kept exactly as it is, so the `.sqlx` metadata of the query stays valid.
+/// `query_files` are the entries of `queries/` already there, a new file does not replace one.
+/// `None` if the call cannot take the SQL from there: sqlx macros only accept literals,
+/// and only the sqlx query macros have a `query_file` counterpart.
+pub fn extract_query(
+    source_txt: &str,
+    sql_node: &SqlNode,
+    target: ExtractTarget,
+    query_files: &[String],
+) -> Option<QueryExtraction> {
+    let name = sql_node
+        .call
+        .enclosing_fn
+        .as_deref()
+        .and_then(|f| f.rsplit("::").next())
+        .unwrap_or("query");
+    match target {
+        ExtractTarget::Const => extract_to_const(source_txt, sql_node, name),
+        ExtractTarget::File => extract_to_file(source_txt, sql_node, name, query_files),
+    }
+}
+
+fn extract_to_const(source_txt: &str, sql_node: &SqlNode, name: &str) -> Option<QueryExtraction> {
+    if sql_node.call.is_macro {
+        return None;
+    }
+    let (insert_at, indent) = enclosing_item_start(source_txt, &sql_node.literal.range)?;
+    let const_name = unique_const_name(source_txt, &format!("{}_SQL", name.to_uppercase()));
+    let value = &sql_node.literal.value;
+    let literal = encode(
+        LiteralKind::RawStr {
+            hashes: raw_hashes(value).max(1),
+        },
+        value,
+    );
+    Some(QueryExtraction {
+        edits: vec![
+            TextEdit {
+                range: Range {
+                    start: insert_at,
+                    end: insert_at,
+                },
+                new_text: format!("const {}: &str = {};\n\n{}", const_name, literal, indent),
+            },
+            TextEdit {
+                range: sql_node.literal.range,
+                new_text: const_name,
+            },
+        ],
+        files: vec![],
+    })
+}
+
+fn extract_to_file(
+    source_txt: &str,
+    sql_node: &SqlNode,
+    name: &str,
+    query_files: &[String],
+) -> Option<QueryExtraction> {
+    let call = &sql_node.call;
+    if !call.is_macro {
+        return None;
+    }
+    let file_macro = FILE_MACROS
+        .iter()
+        .find(|(inline, _)| *inline == call.function_name())
+        .map(|(_, file)| *file)?;
+    let path = unique_query_file(source_txt, name, query_files);
+    let mut macro_path = call.path.clone();
+    *macro_path.last_mut()? = file_macro.to_string();
+    Some(QueryExtraction {
+        edits: vec![
+            TextEdit {
+                range: call.path_range,
+                new_text: format!("{}!", macro_path.join("::")),
+            },
+            TextEdit {
+                range: sql_node.literal.range,
+                new_text: format!("\"{}\"", path),
+            },
+        ],
+        files: vec![NewFile {
+            path,
+            content: sql_node.literal.value.clone(),
+        }],
+    })
+}
+
+// ex) LIST_SQL, LIST_SQL_2 if there is already a LIST_SQL
+fn unique_const_name(source_txt: &str, name: &str) -> String {
+    let taken = |name: &str| {
+        source_txt
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|word| word == name)
+    };
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|i| format!("{}_{}", name, i))
+        .find(|name| !taken(name))
+        .unwrap_or_default()
+}
+
+// ex) queries/list.sql, queries/list_2.sql if there is already a list.sql in `queries/` or
+// another query of the source reads it
+fn unique_query_file(source_txt: &str, name: &str, query_files: &[String]) -> String {
+    let path = |name: &str| format!("queries/{}.sql", name);
+    let taken = |name: &str| {
+        query_files.contains(&format!("{}.sql", name))
+            || source_txt.contains(&format!("\"{}\"", path(name)))
+    };
+    if !taken(name) {
+        return path(name);
+    }
+    (2..)
+        .map(|i| format!("{}_{}", name, i))
+        .find(|name| !taken(name))
+        .map(|name| path(&name))
+        .unwrap_or_default()
+}
+
+/// Start and indentation of the item of a module that contains `range`, ex) the `impl` of a
+/// method, where a `const` can be inserted. `None` if the source cannot be parsed.
+fn enclosing_item_start(source_txt: &str, range: &Range) -> Option<(Position, String)> {
+    let ast = syn::parse_file(source_txt).ok()?;
+    let mut items = &ast.items;
+    loop {
+        let item = items.iter().find(|item| {
+            let item_range = span_range(item.span());
+            item_range.start <= range.start && range.end <= item_range.end
+        })?;
+        match item {
+            syn::Item::Mod(syn::ItemMod {
+                content: Some((_, mod_items)),
+                ..
+            }) => items = mod_items,
+            item => {
+                let start = span_range(item.span()).start;
+                let indent = " ".repeat(start.character);
+                return Some((start, indent));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{default_configs, extract_sql_nodes, Config};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn extract_to_const_above_the_impl() {
+        let source_txt = r#"
+mod repository {
+    impl TodoRepository {
+        async fn list(&self) {
+            sqlx::query("SELECT id FROM todos WHERE title = 'a \"b\"'")
+                .fetch_all(&self.pool)
+                .await;
+        }
+    }
+}
+"#;
+        let configs = vec![Config {
+            functionName: "query".to_string(),
+            sqlArgNo: 0,
+            isMacro: false,
+            dialect: None,
+        }];
+        let sql_nodes = extract_sql_nodes(source_txt, &configs, Dialect::PostgreSql).unwrap();
+        let result = extract_query(source_txt, &sql_nodes[0], ExtractTarget::Const, &[]).unwrap();
+        assert_eq!(
+            result.edits[0],
+            TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 2,
+                        character: 4,
+                    },
+                    end: Position {
+                        line: 2,
+                        character: 4,
+                    },
+                },
+                new_text: "const LIST_SQL: &str = r#\"SELECT id FROM todos WHERE title = 'a \"b\"'\"#;\n\n    "
+                    .to_string(),
+            }
+        );
+        assert_eq!(result.edits[1].range, sql_nodes[0].literal.range);
+        assert_eq!(result.edits[1].new_text, "LIST_SQL");
+
+        // sqlx macros only take literals
+        let source_txt = "fn list() { sqlx::query!(\"SELECT 1\"); }";
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql).unwrap();
+        assert_eq!(
+            extract_query(source_txt, &sql_nodes[0], ExtractTarget::Const, &[]),
+            None
+        );
+    }
+
+    #[test]
+    fn extract_to_file_with_query_file_macro() {
+        let source_txt = r##"
+async fn list_todos(pool: &PgPool) {
+    sqlx::query_as!(Todo, r#"
+        SELECT id, description FROM todos
+    "#)
+}
+"##;
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql).unwrap();
+        let result = extract_query(source_txt, &sql_nodes[0], ExtractTarget::File, &[]).unwrap();
+        assert_eq!(
+            result.files,
+            vec![NewFile {
+                path: "queries/list_todos.sql".to_string(),
+                content: "\n        SELECT id, description FROM todos\n    ".to_string(),
+            }]
+        );
+        assert_eq!(result.edits[0].range, sql_nodes[0].call.path_range);
+        assert_eq!(result.edits[0].new_text, "sqlx::query_file_as!");
+        assert_eq!(result.edits[1].new_text, "\"queries/list_todos.sql\"");
+    }
+
+    #[test]
+    fn extract_to_file_without_replacing_one() {
+        let source_txt = r#"
+async fn list_todos(pool: &PgPool) {
+    sqlx::query_file!("queries/list_todos.sql");
+    sqlx::query!("SELECT id FROM todos");
+}
+"#;
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql).unwrap();
+        let path = |query_files: &[String]| {
+            extract_query(source_txt, &sql_nodes[0], ExtractTarget::File, query_files)
+                .unwrap()
+                .files[0]
+                .path
+                .clone()
+        };
+        // read by the first query
+        assert_eq!(path(&[]), "queries/list_todos_2.sql");
+        assert_eq!(
+            path(&["list_todos_2.sql".to_string()]),
+            "queries/list_todos_3.sql"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use crate::dialect::Dialect;
+use crate::workspace::{read_rust_files, Location, SourceFile};
+use crate::{extract_sql_nodes, Config, SqlNode};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::path::Path;
+
+/// Queries of the workspace that have the same fingerprint.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    pub fingerprint: String,
+    pub normalized: String,
+    pub locations: Vec<Location>,
+}
+
+/// SQL with single spaces between tokens, unquoted keywords and identifiers in lower case
+/// (the tokenizer cannot tell `id` the column from `ID` the keyword), and `?` for every
+/// literal value and placeholder. `None` if the SQL cannot be tokenized.
+pub fn normalize(sql: &str, dialect: Dialect) -> Option<String> {
+    let tokens = Tokenizer::new(&*dialect.parser_dialect(), sql)
+        .tokenize()
+        .ok()?;
+    let mut words: Vec<String> = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Whitespace(_) | Token::EOF => None,
+            Token::Word(word) if word.quote_style.is_none() => Some(word.value.to_lowercase()),
+            Token::Number(..)
+            | Token::SingleQuotedString(_)
+            | Token::DoubleQuotedString(_)
+            | Token::TripleSingleQuotedString(_)
+            | Token::TripleDoubleQuotedString(_)
+            | Token::DollarQuotedString(_)
+            | Token::SingleQuotedByteStringLiteral(_)
+            | Token::DoubleQuotedByteStringLiteral(_)
+            | Token::NationalStringLiteral(_)
+            | Token::EscapedStringLiteral(_)
+            | Token::UnicodeStringLiteral(_)
+            | Token::HexStringLiteral(_)
+            | Token::Placeholder(_) => Some("?".to_string()),
+            _ => Some(token.to_string()),
+        })
+        .collect();
+    // `SELECT 1;` is the same query as `SELECT 1`
+    while words.last().is_some_and(|w| w == ";") {
+        words.pop();
+    }
+    Some(words.join(" "))
+}
+
+/// Short hash of the normalized SQL.
+pub fn fingerprint(sql: &str, dialect: Dialect) -> Option<String> {
+    let normalized = normalize(sql, dialect)?;
+    Some(hex::encode(Sha256::digest(normalized.as_bytes()))[..16].to_string())
+}
+
+/// Fill `fingerprint` of each node.
+pub fn link(sql_nodes: &mut [SqlNode]) {
+    for sql_node in sql_nodes.iter_mut() {
+        sql_node.fingerprint = fingerprint(&sql_node.literal.value, sql_node.dialect);
+    }
+}
+
+/// Group the queries of `sources` by fingerprint, keeping the groups with more than one query.
+/// Files that cannot be parsed are skipped.
+pub fn find_duplicates(
+    sources: &[SourceFile],
+    configs: &[Config],
+    dialect: Dialect,
+) -> Vec<DuplicateGroup> {
+    let mut groups: Vec<DuplicateGroup> = Vec::new();
+    for source in sources {
+        let mut sql_nodes = match extract_sql_nodes(&source.content, configs, dialect) {
+            Ok(sql_nodes) => sql_nodes,
+            Err(_) => continue,
+        };
+        link(&mut sql_nodes);
+        for sql_node in sql_nodes {
+            let fingerprint = match sql_node.fingerprint {
+                Some(fingerprint) => fingerprint,
+                None => continue,
+            };
+            let location = Location {
+                path: source.path.clone(),
+                range: sql_node.literal.range,
+            };
+            match groups.iter_mut().find(|g| g.fingerprint == fingerprint) {
+                Some(group) => group.locations.push(location),
+                None => groups.push(DuplicateGroup {
+                    fingerprint,
+                    normalized: normalize(&sql_node.literal.value, sql_node.dialect)
+                        .unwrap_or_default(),
+                    locations: vec![location],
+                }),
+            }
+        }
+    }
+    groups.retain(|g| g.locations.len() > 1);
+    groups
+}
+
+/// `find_duplicates` for the Rust files under `root`.
+pub fn duplicates_in_workspace(
+    root: &Path,
+    configs: &[Config],
+    dialect: Dialect,
+) -> std::io::Result<Vec<DuplicateGroup>> {
+    Ok(find_duplicates(&read_rust_files(root)?, configs, dialect))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::default_configs;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn normalize_case_whitespace_and_values() {
+        assert_eq!(
+            normalize(
+                "select ID, Description\n  FROM todos WHERE id = $1 AND title = 'a';",
+                Dialect::PostgreSql
+            ),
+            normalize(
+                "SELECT id, description FROM TODOS where id = ? and title = 'b'",
+                Dialect::MySql
+            ),
+        );
+        assert_eq!(
+            normalize("SELECT \"Id\" FROM todos LIMIT 10", Dialect::PostgreSql).unwrap(),
+            "select \"Id\" from todos limit ?"
+        );
+    }
+
+    #[test]
+    fn group_duplicates_across_files() {
+        let sources = vec![
+            SourceFile {
+                path: "src/a.rs".to_string(),
+                content: r#"
+fn a() {
+    sqlx::query!("SELECT id, description, done FROM todos");
+    sqlx::query!("SELECT id FROM todos");
+}
+"#
+                .to_string(),
+            },
+            SourceFile {
+                path: "src/b.rs".to_string(),
+                content: r##"
+fn b() {
+    sqlx::query!(r#"
+        select id, description, done
+        from todos
+    "#);
+}
+"##
+                .to_string(),
+            },
+        ];
+        let result = find_duplicates(&sources, &default_configs(), Dialect::PostgreSql);
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].normalized,
+            "select id , description , done from todos"
+        );
+        let paths: Vec<&str> = result[0]
+            .locations
+            .iter()
+            .map(|l| l.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["src/a.rs", "src/b.rs"]);
+    }
+}
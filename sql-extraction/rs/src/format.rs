@@ -0,0 +1,989 @@
+use crate::dialect::Dialect;
+use crate::edit::{text_of, TextEdit};
+use crate::layout::{LayoutStyle, LiteralLayout};
+use crate::literal::{advance, LiteralKind};
+use crate::workspace::{read_rust_files, SourceFile};
+use crate::{extract_sql_nodes, Config, Range, SqlNode};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer, Whitespace};
+use std::path::Path;
+
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct FormatOptions {
+    /// one level of indentation, in the SQL and relative to the call
+    pub indent: String,
+    /// turn `"..."` into a raw string when the formatted query has several lines or quotes
+    pub promote_raw_strings: bool,
+    /// put a query on one line in `"..."`, turning a raw string back, when it fits in at most
+    /// this many chars and needs no escaping
+    pub demote_max_length: Option<usize>,
+    /// where multi-line SQL goes in its literal
+    pub layout: LayoutStyle,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: "    ".to_string(),
+            promote_raw_strings: true,
+            demote_max_length: None,
+            layout: LayoutStyle::default(),
+        }
+    }
+}
+
+// keyword sequences that start a clause on their own line, with their content indented below
+const CLAUSES: [&[&str]; 20] = [
+    &["GROUP", "BY"],
+    &["ORDER", "BY"],
+    &["INSERT", "INTO"],
+    &["REPLACE", "INTO"],
+    &["DELETE", "FROM"],
+    &["SELECT"],
+    &["FROM"],
+    &["WHERE"],
+    &["HAVING"],
+    &["LIMIT"],
+    &["OFFSET"],
+    &["VALUES"],
+    &["SET"],
+    &["RETURNING"],
+    &["UPDATE"],
+    &["INSERT"],
+    &["DELETE"],
+    &["WITH"],
+    &["WINDOW"],
+    &["QUALIFY"],
+];
+
+// keyword sequences between two queries, alone on their line
+const SET_OPERATIONS: [&[&str]; 6] = [
+    &["UNION", "ALL"],
+    &["UNION"],
+    &["INTERSECT", "ALL"],
+    &["INTERSECT"],
+    &["EXCEPT", "ALL"],
+    &["EXCEPT"],
+];
+
+const JOIN_MODIFIERS: [&str; 7] = [
+    "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "NATURAL", "OUTER",
+];
+
+/// Format a query (or a script) with one clause per line and the content of each clause
+/// indented below it. Tokens, so keyword case, comments and string literals, and everything
+/// inside non-query parentheses are kept as written. `None` if the SQL cannot be tokenized.
+pub fn format_sql(sql: &str, dialect: Dialect, options: &FormatOptions) -> Option<String> {
+    Some(format_sql_lines(sql, dialect, options)?.join("\n"))
+}
+
+/// Lines of `format_sql`, split at the line breaks of the formatter only: a string literal
+/// or a comment of several lines is kept whole in its line.
+pub fn format_sql_lines(
+    sql: &str,
+    dialect: Dialect,
+    options: &FormatOptions,
+) -> Option<Vec<String>> {
+    render(sql, dialect, options, false)
+}
+
+/// The query of `format_sql` on one line, with its clauses separated by a space. `None` if
+/// the SQL cannot be tokenized or has a `--` comment.
+pub fn format_sql_one_line(sql: &str, dialect: Dialect, options: &FormatOptions) -> Option<String> {
+    render(sql, dialect, options, true)?.pop()
+}
+
+fn render(
+    sql: &str,
+    dialect: Dialect,
+    options: &FormatOptions,
+    one_line: bool,
+) -> Option<Vec<String>> {
+    let tokens = Tokenizer::new(&*dialect.parser_dialect(), sql)
+        .with_unescape(false)
+        .tokenize_with_location()
+        .ok()?;
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(sql.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let mut items: Vec<Item> = Vec::new();
+    let mut whitespace = false;
+    for TokenWithSpan { token, span } in tokens {
+        match token {
+            Token::Whitespace(Whitespace::Space | Whitespace::Newline | Whitespace::Tab) => {
+                whitespace = true
+            }
+            Token::EOF => {}
+            token => {
+                // `Display` of some tokens differs from the source, ex) `E'\n'` is unescaped
+                let text = &sql[byte_offset(sql, &line_starts, span.start)
+                    ..byte_offset(sql, &line_starts, span.end)];
+                items.push(Item {
+                    token,
+                    text: text.to_string(),
+                    whitespace,
+                });
+                whitespace = false;
+            }
+        }
+    }
+
+    let mut writer = Writer {
+        lines: vec![String::new()],
+        indent: &options.indent,
+        level: 0,
+        line_start: true,
+        one_line,
+        previous: None,
+    };
+    let mut blocks = vec![Block {
+        query: true,
+        base: 0,
+        content: 0,
+    }];
+    let mut in_between = false;
+    let mut i = 0;
+    while i < items.len() {
+        let item = &items[i];
+        let block = blocks.last_mut()?;
+        let query_context = block.query;
+        match &item.token {
+            Token::Whitespace(Whitespace::SingleLineComment { .. }) => {
+                if one_line {
+                    return None;
+                }
+                writer.word(item.text.trim_end(), true);
+                writer.newline(block.content);
+            }
+            Token::LParen => {
+                let query = items.get(i + 1).is_some_and(|next| {
+                    matches!(keyword(&next.token).as_deref(), Some("SELECT" | "WITH"))
+                });
+                writer.item(item);
+                let base = writer.level + 1;
+                blocks.push(Block {
+                    query,
+                    base,
+                    content: base,
+                });
+            }
+            Token::RParen => {
+                if blocks.len() > 1 {
+                    let closed = blocks.pop()?;
+                    if closed.query {
+                        writer.newline(closed.base - 1);
+                    }
+                }
+                writer.item(item);
+            }
+            Token::SemiColon => {
+                writer.item(item);
+                blocks.truncate(1);
+                blocks[0].content = 0;
+                if i + 1 < items.len() && !one_line {
+                    // a blank line between statements
+                    writer.newline(0);
+                    writer.lines.push(String::new());
+                }
+            }
+            Token::Comma if query_context => {
+                writer.item(item);
+                writer.newline(block.content);
+            }
+            _ if query_context => {
+                if let Some(words) = match_words(&items[i..], &SET_OPERATIONS) {
+                    writer.newline(block.base);
+                    writer.words(&items[i..i + words]);
+                    writer.newline(block.base);
+                    block.content = block.base;
+                    i += words;
+                    continue;
+                }
+                if let Some(words) = match_words(&items[i..], &CLAUSES)
+                    .filter(|_| is_clause(&item.token, writer.previous.as_ref()))
+                {
+                    writer.newline(block.base);
+                    writer.words(&items[i..i + words]);
+                    writer.newline(block.base + 1);
+                    block.content = block.base + 1;
+                    i += words;
+                    continue;
+                }
+                if let Some(words) = match_join(&items[i..]) {
+                    writer.newline(block.content);
+                    writer.words(&items[i..i + words]);
+                    i += words;
+                    continue;
+                }
+                match keyword(&item.token).as_deref() {
+                    Some("BETWEEN") => in_between = true,
+                    Some("AND") if in_between => in_between = false,
+                    Some("AND" | "OR") => writer.newline(block.content),
+                    _ => {}
+                }
+                writer.item(item);
+            }
+            _ => writer.item(item),
+        }
+        i += 1;
+    }
+    let mut lines = writer.lines;
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+    if let Some(last) = lines.last_mut() {
+        last.truncate(last.trim_end().len());
+    }
+    Some(lines)
+}
+
+struct Item {
+    token: Token,
+    /// source text of the token
+    text: String,
+    /// preceded by whitespace
+    whitespace: bool,
+}
+
+// byte offset in `sql` of a location of the tokenizer, whose lines and columns are chars from 1
+fn byte_offset(sql: &str, line_starts: &[usize], location: Location) -> usize {
+    let start = line_starts[location.line as usize - 1];
+    sql[start..]
+        .char_indices()
+        .nth(location.column as usize - 1)
+        .map_or(sql.len(), |(i, _)| start + i)
+}
+
+struct Block {
+    /// a query, `false` for parentheses that are kept on one line, ex) `count(*)`
+    query: bool,
+    /// level of the clause keywords
+    base: usize,
+    /// level of the content of the current clause
+    content: usize,
+}
+
+struct Writer<'a> {
+    /// the last one is being written
+    lines: Vec<String>,
+    indent: &'a str,
+    level: usize,
+    line_start: bool,
+    /// line breaks are written as the space between the tokens
+    one_line: bool,
+    previous: Option<Token>,
+}
+
+impl Writer<'_> {
+    fn newline(&mut self, level: usize) {
+        self.level = level;
+        if self.line_start || self.one_line {
+            self.line_start = true;
+            return;
+        }
+        let line = self.line();
+        line.truncate(line.trim_end_matches(' ').len());
+        self.lines.push(String::new());
+        self.line_start = true;
+    }
+
+    fn words(&mut self, items: &[Item]) {
+        let text: Vec<&str> = items.iter().map(|item| item.text.as_str()).collect();
+        let space = space_between(self.previous.as_ref(), &items[0].token, true);
+        self.word(&text.join(" "), space);
+        self.previous = items.last().map(|item| item.token.clone());
+    }
+
+    fn line(&mut self) -> &mut String {
+        self.lines.last_mut().expect("a writer has a line")
+    }
+
+    fn word(&mut self, text: &str, space: bool) {
+        if self.line_start && self.one_line {
+            if space && !self.line().is_empty() {
+                self.line().push(' ');
+            }
+            self.line_start = false;
+        } else if self.line_start {
+            let indent = self.indent.repeat(self.level);
+            self.line().push_str(&indent);
+            self.line_start = false;
+        } else if space {
+            self.line().push(' ');
+        }
+        self.line().push_str(text);
+    }
+
+    fn item(&mut self, item: &Item) {
+        let space = space_between(self.previous.as_ref(), &item.token, item.whitespace);
+        self.word(&item.text, space);
+        self.previous = Some(item.token.clone());
+    }
+}
+
+fn space_between(previous: Option<&Token>, token: &Token, whitespace: bool) -> bool {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return false,
+    };
+    if matches!(
+        previous,
+        Token::LParen | Token::Period | Token::DoubleColon | Token::LBracket
+    ) {
+        return false;
+    }
+    if matches!(
+        token,
+        Token::Comma
+            | Token::SemiColon
+            | Token::RParen
+            | Token::Period
+            | Token::DoubleColon
+            | Token::RBracket
+    ) {
+        return false;
+    }
+    // `count(*)` and `todos (id)` are kept as written
+    if matches!(token, Token::LParen | Token::LBracket) && matches!(previous, Token::Word(_)) {
+        return whitespace;
+    }
+    // a sign is followed by a space only if it was, ex) `= -1`, `a - 1`
+    !matches!(previous, Token::Minus | Token::Plus) || whitespace
+}
+
+// uppercase value of an unquoted word
+fn keyword(token: &Token) -> Option<String> {
+    match token {
+        Token::Word(word) if word.quote_style.is_none() => Some(word.value.to_uppercase()),
+        _ => None,
+    }
+}
+
+// number of words of the longest of `candidates` at the start of `items`
+fn match_words(items: &[Item], candidates: &[&[&str]]) -> Option<usize> {
+    candidates
+        .iter()
+        .find(|candidate| {
+            candidate.len() <= items.len()
+                && candidate
+                    .iter()
+                    .zip(items)
+                    .all(|(c, item)| keyword(&item.token).as_deref() == Some(*c))
+        })
+        .map(|candidate| candidate.len())
+}
+
+// ex) `LEFT OUTER JOIN`, but not the `left(...)` function
+fn match_join(items: &[Item]) -> Option<usize> {
+    for (i, item) in items.iter().enumerate() {
+        let keyword = keyword(&item.token)?;
+        if keyword == "JOIN" {
+            return Some(i + 1);
+        }
+        if !JOIN_MODIFIERS.contains(&keyword.as_str()) {
+            return None;
+        }
+    }
+    None
+}
+
+// `DO UPDATE SET`, `FOR UPDATE` and `TIMESTAMP WITH TIME ZONE` do not start a clause
+fn is_clause(token: &Token, previous: Option<&Token>) -> bool {
+    let previous = previous.and_then(keyword);
+    match keyword(token).as_deref() {
+        Some("UPDATE") => !matches!(previous.as_deref(), Some("DO" | "FOR" | "KEY")),
+        Some("WITH") => !matches!(previous.as_deref(), Some("TIMESTAMP" | "TIME" | "ZONE")),
+        Some("FROM") => !matches!(previous.as_deref(), Some("DISTINCT")),
+        Some("SET") => !matches!(previous.as_deref(), Some("CHARACTER" | "CHAR")),
+        _ => true,
+    }
+}
+
+/// Edits formatting the query of a node, re-encoded for its literal kind: escaped in `"..."`,
+/// with as many `#` as the content needs in raw strings. The kind itself changes as
+/// configured, see `literal_kind`. Only the changed part of the literal, delimiters
+/// included, is replaced. `None` if the SQL cannot be tokenized.
+pub fn format_edits(
+    source_txt: &str,
+    sql_node: &SqlNode,
+    options: &FormatOptions,
+) -> Option<Vec<TextEdit>> {
+    let old_literal = text_of(source_txt, &sql_node.literal.range);
+    let new_literal = format_literal(source_txt, sql_node, options)?;
+    Some(
+        minimal_edit(&old_literal, &new_literal, &sql_node.literal.range)
+            .into_iter()
+            .collect(),
+    )
+}
+
+/// Source text of the literal of a node once formatted, delimiters included.
+/// `None` if the SQL cannot be tokenized.
+pub fn format_literal(
+    source_txt: &str,
+    sql_node: &SqlNode,
+    options: &FormatOptions,
+) -> Option<String> {
+    let value = &sql_node.literal.value;
+    if value.trim().is_empty() {
+        return Some(text_of(source_txt, &sql_node.literal.range));
+    }
+    if let Some(short) = short_query(value, sql_node.dialect, options) {
+        return Some(encode(LiteralKind::Str, &short));
+    }
+    let lines = format_sql_lines(value, sql_node.dialect, options)?;
+    let formatted = lines.join("\n");
+    let kind = literal_kind(sql_node.literal.kind, &formatted, options);
+    let body = LiteralLayout::derive(
+        source_txt,
+        sql_node,
+        &options.layout,
+        &options.indent,
+        lines.len() > 1,
+    )
+    .apply(&lines);
+
+    Some(match (sql_node.literal.kind, kind) {
+        (LiteralKind::Str, LiteralKind::RawStr { .. }) => encode(
+            LiteralKind::RawStr {
+                hashes: raw_hashes(&body),
+            },
+            &body,
+        ),
+        _ => encode(kind, &body),
+    })
+}
+
+/// A literal whose query is not formatted, as written and formatted.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Unformatted {
+    pub path: String,
+    pub range: Range,
+    pub current: String,
+    pub formatted: String,
+}
+
+/// Format the queries of `sources` in memory and return the literals that would change.
+/// Formatting is idempotent, so formatted sources report nothing. Files that cannot be
+/// parsed are skipped.
+pub fn check_formatting(
+    sources: &[SourceFile],
+    configs: &[Config],
+    dialect: Dialect,
+    options: &FormatOptions,
+) -> Vec<Unformatted> {
+    let mut unformatted = Vec::new();
+    for source in sources {
+        let sql_nodes = match extract_sql_nodes(&source.content, configs, dialect) {
+            Ok(sql_nodes) => sql_nodes,
+            Err(_) => continue,
+        };
+        for sql_node in &sql_nodes {
+            let current = text_of(&source.content, &sql_node.literal.range);
+            match format_literal(&source.content, sql_node, options) {
+                Some(formatted) if formatted != current => unformatted.push(Unformatted {
+                    path: source.path.clone(),
+                    range: sql_node.literal.range,
+                    current,
+                    formatted,
+                }),
+                _ => {}
+            }
+        }
+    }
+    unformatted
+}
+
+/// Check the formatting of the queries of the Rust files under `root`, see `check_formatting`.
+pub fn check_workspace(
+    root: &Path,
+    configs: &[Config],
+    dialect: Dialect,
+    options: &FormatOptions,
+) -> std::io::Result<Vec<Unformatted>> {
+    Ok(check_formatting(
+        &read_rust_files(root)?,
+        configs,
+        dialect,
+        options,
+    ))
+}
+
+/// The query on one line if `demote_max_length` is set and it fits in `"..."` unescaped.
+fn short_query(sql: &str, dialect: Dialect, options: &FormatOptions) -> Option<String> {
+    let max = options.demote_max_length?;
+    format_sql_one_line(sql, dialect, options)
+        .filter(|one_line| one_line.chars().count() <= max && !one_line.contains(['\n', '"', '\\']))
+}
+
+/// Kind of the literal holding the formatted query of a `current` literal: a raw string for
+/// a query with several lines or quotes, which would be hard to read escaped. Short queries
+/// go back to `"..."` before, see `demote_max_length`.
+pub fn literal_kind(current: LiteralKind, formatted: &str, options: &FormatOptions) -> LiteralKind {
+    let needs_raw = formatted.contains('\n') || formatted.contains('"');
+    match current {
+        LiteralKind::Str if needs_raw && options.promote_raw_strings => LiteralKind::RawStr {
+            hashes: raw_hashes(formatted),
+        },
+        kind => kind,
+    }
+}
+
+/// Source text of a literal of `kind` holding `value`. Raw strings get more `#` if needed.
+pub fn encode(kind: LiteralKind, value: &str) -> String {
+    match kind {
+        LiteralKind::Str => format!("\"{}\"", kind.escape(value)),
+        LiteralKind::RawStr { hashes } => {
+            let hashes = "#".repeat(hashes.max(raw_hashes(value)));
+            format!("r{}\"{}\"{}", hashes, value, hashes)
+        }
+    }
+}
+
+/// Fewest `#` of a raw string that can hold `value`.
+pub fn raw_hashes(value: &str) -> usize {
+    value
+        .match_indices('"')
+        .map(|(i, _)| value[i + 1..].chars().take_while(|c| *c == '#').count() + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// The edit turning `old` (the text of `range`) into `new`, without the common start and end.
+pub(crate) fn minimal_edit(old: &str, new: &str, range: &Range) -> Option<TextEdit> {
+    if old == new {
+        return None;
+    }
+    let old_chars: Vec<char> = old.chars().collect();
+    let new_chars: Vec<char> = new.chars().collect();
+    let common_start = old_chars
+        .iter()
+        .zip(&new_chars)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let common_end = old_chars[common_start..]
+        .iter()
+        .rev()
+        .zip(new_chars[common_start..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let start: String = old_chars[..common_start].iter().collect();
+    let end: String = old_chars[..old_chars.len() - common_end].iter().collect();
+    Some(TextEdit {
+        range: Range {
+            start: advance(&range.start, &start),
+            end: advance(&range.start, &end),
+        },
+        new_text: new_chars[common_start..new_chars.len() - common_end]
+            .iter()
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{default_configs, extract_sql_nodes};
+    use pretty_assertions::assert_eq;
+
+    fn format(sql: &str) -> String {
+        let options = FormatOptions {
+            indent: "  ".to_string(),
+            ..Default::default()
+        };
+        let formatted = format_sql(sql, Dialect::PostgreSql, &options).unwrap();
+        // formatting is idempotent
+        assert_eq!(
+            format_sql(&formatted, Dialect::PostgreSql, &options).unwrap(),
+            formatted
+        );
+        formatted
+    }
+
+    #[test]
+    fn format_clauses_and_subqueries() {
+        assert_eq!(
+            format(
+                "select t.id, count(*) from todos t left join users u on u.id=t.user_id \
+                 where t.done = $1 and t.id between 1 and -10 \
+                 and t.id in (select id from tags where name = 'it''s') group by t.id limit 10"
+            ),
+            "select
+  t.id,
+  count(*)
+from
+  todos t
+  left join users u on u.id = t.user_id
+where
+  t.done = $1
+  and t.id between 1 and -10
+  and t.id in (
+    select
+      id
+    from
+      tags
+    where
+      name = 'it''s'
+  )
+group by
+  t.id
+limit
+  10"
+        );
+        assert_eq!(
+            format(
+                "INSERT INTO todos (description) VALUES ($1) \
+                 ON CONFLICT (id) DO UPDATE SET done = true RETURNING id; SELECT 1"
+            ),
+            "INSERT INTO
+  todos (description)
+VALUES
+  ($1) ON CONFLICT (id) DO UPDATE
+SET
+  done = true
+RETURNING
+  id;
+
+SELECT
+  1"
+        );
+    }
+
+    #[test]
+    fn keep_string_literals_as_written() {
+        // `E'\n'` is a backslash and an `n`, not a newline
+        let sql =
+            r"select E'\n', U&'d\0061t', N'it''s', X'ff', $tag$ a $tag$ from t where x = E'\n'";
+        let formatted = format(sql);
+        assert_eq!(
+            formatted,
+            r"select
+  E'\n',
+  U&'d\0061t',
+  N'it''s',
+  X'ff',
+  $tag$ a $tag$
+from
+  t
+where
+  x = E'\n'"
+        );
+    }
+
+    fn apply(source_txt: &str, edits: &[TextEdit]) -> String {
+        assert!(edits.len() <= 1);
+        match edits.first() {
+            None => source_txt.to_string(),
+            Some(edit) => {
+                let lines: Vec<&str> = source_txt.split('\n').collect();
+                let before: String = lines[..edit.range.start.line]
+                    .iter()
+                    .map(|l| format!("{}\n", l))
+                    .collect::<String>()
+                    + &lines[edit.range.start.line]
+                        .chars()
+                        .take(edit.range.start.character)
+                        .collect::<String>();
+                let after: String = lines[edit.range.end.line]
+                    .chars()
+                    .skip(edit.range.end.character)
+                    .collect::<String>()
+                    + &lines[edit.range.end.line + 1..]
+                        .iter()
+                        .map(|l| format!("\n{}", l))
+                        .collect::<String>();
+                format!("{}{}{}", before, edit.new_text, after)
+            }
+        }
+    }
+
+    fn format_source_with(source_txt: &str, options: &FormatOptions) -> String {
+        let sql_nodes =
+            extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql).unwrap();
+        let edits = format_edits(source_txt, &sql_nodes[0], options).unwrap();
+        apply(source_txt, &edits)
+    }
+
+    fn format_source(source_txt: &str) -> String {
+        format_source_with(source_txt, &FormatOptions::default())
+    }
+
+    #[test]
+    fn format_edits_respect_literal_kinds() {
+        // quotes are escaped in a plain string
+        assert_eq!(
+            format_source_with(
+                r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT id AS \"id!\" FROM todos");
+}
+"#,
+                &FormatOptions {
+                    promote_raw_strings: false,
+                    ..Default::default()
+                }
+            ),
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT
+            id AS \"id!\"
+        FROM
+            todos");
+}
+"#
+        );
+
+        let source_txt = r##"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r#"
+        select id as "id!" from todos
+    "#);
+}
+"##;
+        let formatted = format_source(source_txt);
+        assert_eq!(
+            formatted,
+            r##"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r#"
+        select
+            id as "id!"
+        from
+            todos
+    "#);
+}
+"##
+        );
+        // already formatted
+        assert_eq!(format_source(&formatted), formatted);
+
+        // a raw string needs one more `#` than the longest `"#...` of its content
+        assert_eq!(
+            encode(LiteralKind::RawStr { hashes: 0 }, "SELECT '\"#' AS \"a\""),
+            "r##\"SELECT '\"#' AS \"a\"\"##"
+        );
+        assert_eq!(
+            encode(LiteralKind::RawStr { hashes: 1 }, "SELECT 1"),
+            "r#\"SELECT 1\"#"
+        );
+    }
+
+    #[test]
+    fn promote_and_demote_literal_kinds() {
+        // several lines and quotes: the fewest `#` that are safe
+        assert_eq!(
+            format_source(
+                r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT id AS \"id!\" FROM todos");
+}
+"#
+            ),
+            r##"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r#"SELECT
+            id AS "id!"
+        FROM
+            todos"#);
+}
+"##
+        );
+        assert_eq!(
+            format_source(
+                r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT id FROM todos");
+}
+"#
+            ),
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r"SELECT
+            id
+        FROM
+            todos");
+}
+"#
+        );
+
+        // a short query back to `"..."` on one line when configured
+        let source_txt = r##"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r#"
+        select id, done from todos where id in (select todo_id from tags)
+    "#);
+}
+"##;
+        let options = FormatOptions {
+            demote_max_length: Some(80),
+            ..Default::default()
+        };
+        let demoted = format_source_with(source_txt, &options);
+        assert_eq!(
+            demoted,
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("select id, done from todos where id in (select todo_id from tags)");
+}
+"#
+        );
+        // and kept there
+        assert_eq!(format_source_with(&demoted, &options), demoted);
+        // too long
+        let options = FormatOptions {
+            demote_max_length: Some(40),
+            ..Default::default()
+        };
+        assert_eq!(
+            format_source_with(source_txt, &options),
+            format_source(source_txt)
+        );
+    }
+
+    #[test]
+    fn keep_multi_line_string_constants() {
+        // the second line of the constant is part of the value, not indented
+        let source_txt = r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT 'line1
+line2' AS s FROM t");
+}
+"#;
+        let formatted = format_source(source_txt);
+        assert_eq!(
+            formatted,
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r"SELECT
+            'line1
+line2' AS s
+        FROM
+            t");
+}
+"#
+        );
+        assert_eq!(format_source(&formatted), formatted);
+    }
+
+    #[test]
+    fn apply_layout_style() {
+        let options = FormatOptions {
+            layout: LayoutStyle {
+                leading_newline: Some(true),
+                trailing_newline: Some(true),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            format_source_with(
+                r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT id FROM todos");
+}
+"#,
+                &options
+            ),
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r"
+        SELECT
+            id
+        FROM
+            todos
+    ");
+}
+"#
+        );
+    }
+
+    #[test]
+    fn check_formatting_is_idempotent() {
+        let source = SourceFile {
+            path: "src/todos.rs".to_string(),
+            content: r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("select id from todos where done = false and id in (select todo_id from tags)");
+    sqlx::query!(
+        r"
+            SELECT
+                1
+        "
+    );
+}
+"#
+            .to_string(),
+        };
+        let options = FormatOptions::default();
+        let check = |source: &SourceFile| {
+            check_formatting(
+                std::slice::from_ref(source),
+                &default_configs(),
+                Dialect::PostgreSql,
+                &options,
+            )
+        };
+        // the second query is already formatted
+        let result = check(&source);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].path, "src/todos.rs");
+        assert_eq!(result[0].range.start.line, 2);
+
+        let formatted = SourceFile {
+            content: source
+                .content
+                .replace(&result[0].current, &result[0].formatted),
+            ..source
+        };
+        assert_eq!(check(&formatted), vec![]);
+    }
+
+    #[test]
+    fn check_formatting_after_format_edits() {
+        let source_txt = r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!("select id from todos where note = E'\\n' and done = false");
+    sqlx::query!("SELECT 'line1
+line2' AS s FROM t");
+    sqlx::query!(r"select id from todos -- all of them");
+    sqlx::query!("select 1");
+}
+"#;
+        for options in [
+            FormatOptions::default(),
+            FormatOptions {
+                demote_max_length: Some(40),
+                layout: LayoutStyle {
+                    leading_newline: Some(true),
+                    trailing_newline: Some(true),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ] {
+            // from the last query, so the ranges of the others stay valid
+            let mut content = source_txt.to_string();
+            let sql_nodes =
+                extract_sql_nodes(&content, &default_configs(), Dialect::PostgreSql).unwrap();
+            for sql_node in sql_nodes.iter().rev() {
+                let edits = format_edits(&content, sql_node, &options).unwrap();
+                content = apply(&content, &edits);
+            }
+            let formatted = SourceFile {
+                path: "src/todos.rs".to_string(),
+                content,
+            };
+            assert_eq!(
+                check_formatting(
+                    &[formatted],
+                    &default_configs(),
+                    Dialect::PostgreSql,
+                    &options
+                ),
+                vec![]
+            );
+        }
+    }
+}
@@ -0,0 +1,360 @@
+use crate::offline::PreparedQuery;
+use crate::{Position, Range, SqlNode};
+use serde::Serialize;
+
+/// Quick info of a sqlx macro query, from its offline metadata.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Hover {
+    pub range: Range,
+    pub columns: Vec<HoverColumn>,
+    pub parameters: Vec<HoverParameter>,
+    /// markdown
+    pub contents: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HoverColumn {
+    pub name: String,
+    pub sql_type: String,
+    pub rust_type: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct HoverParameter {
+    pub name: String, // ex) "$1"
+    pub sql_type: String,
+    pub rust_type: String,
+}
+
+/// Hover for a position in the query literal or on the macro path of a linked node.
+/// On a `$N` placeholder only that parameter is described. A `$N` missing from
+/// stale metadata gets the hover of the whole query.
+pub fn hover(sql_nodes: &[SqlNode], position: &Position) -> Option<Hover> {
+    let sql_node = sql_nodes.iter().find(|n| {
+        n.offline.is_some()
+            && (n.literal.range.contains(position) || n.call.path_range.contains(position))
+    })?;
+    let prepared = sql_node.offline.as_ref()?;
+    let columns = hover_columns(prepared);
+    let parameters = hover_parameters(prepared);
+
+    let placeholder = sql_node
+        .literal
+        .offset_at(position)
+        .and_then(|offset| placeholder_at(&sql_node.literal.value, offset))
+        .and_then(|(number, start, end)| {
+            let parameter = parameters.get(number.checked_sub(1)?)?;
+            Some((parameter.clone(), start, end))
+        });
+    if let Some((parameter, start, end)) = placeholder {
+        return Some(Hover {
+            range: sql_node.literal.range_of(start, end),
+            contents: format!("```rust\n{}: {}\n```", parameter.name, parameter.rust_type),
+            columns: vec![],
+            parameters: vec![parameter],
+        });
+    }
+
+    let range = if sql_node.call.path_range.contains(position) {
+        sql_node.call.path_range
+    } else {
+        sql_node.literal.range
+    };
+    let contents = contents(&columns, &parameters);
+    Some(Hover {
+        range,
+        columns,
+        parameters,
+        contents,
+    })
+}
+
+fn hover_columns(prepared: &PreparedQuery) -> Vec<HoverColumn> {
+    prepared
+        .columns
+        .iter()
+        .map(|column| {
+            let (name, overrides) = column_override(&column.name);
+            let rust_type = overrides.rust_type.map_or_else(
+                || rust_type(prepared.db_name.as_deref(), &column.type_info),
+                str::to_string,
+            );
+            // sqlx assumes nullable when it cannot tell
+            let rust_type = match overrides.nullable.or(column.nullable) {
+                Some(false) => rust_type,
+                _ => format!("Option<{}>", rust_type),
+            };
+            HoverColumn {
+                name: name.to_string(),
+                sql_type: column.type_info.clone(),
+                rust_type,
+            }
+        })
+        .collect()
+}
+
+fn hover_parameters(prepared: &PreparedQuery) -> Vec<HoverParameter> {
+    prepared
+        .parameters
+        .iter()
+        .enumerate()
+        .map(|(i, sql_type)| HoverParameter {
+            name: format!("${}", i + 1),
+            sql_type: sql_type.clone(),
+            rust_type: rust_type(prepared.db_name.as_deref(), sql_type),
+        })
+        .collect()
+}
+
+fn contents(columns: &[HoverColumn], parameters: &[HoverParameter]) -> String {
+    let mut lines = vec!["```rust".to_string()];
+    if !columns.is_empty() {
+        lines.push("// columns".to_string());
+        lines.extend(
+            columns
+                .iter()
+                .map(|c| format!("{}: {},", c.name, c.rust_type)),
+        );
+    }
+    if !parameters.is_empty() {
+        lines.push("// parameters".to_string());
+        lines.extend(
+            parameters
+                .iter()
+                .map(|p| format!("{}: {},", p.name, p.rust_type)),
+        );
+    }
+    lines.push("```".to_string());
+    lines.join("\n")
+}
+
+// nullability and type set in the name of a column, ex) "id?: i64"
+struct Override<'a> {
+    /// `Some(false)` for "id!", `Some(true)` for "id?"
+    nullable: Option<bool>,
+    rust_type: Option<&'a str>,
+}
+
+fn column_override(name: &str) -> (&str, Override<'_>) {
+    let (name, rust_type) = match name.split_once(':') {
+        Some((name, rust_type)) => (name.trim(), Some(rust_type.trim())),
+        None => (name, None),
+    };
+    let nullable = match name.chars().last() {
+        Some('!') => Some(false),
+        Some('?') => Some(true),
+        _ => None,
+    };
+    (
+        name.trim_end_matches(['!', '?']),
+        Override {
+            nullable,
+            rust_type,
+        },
+    )
+}
+
+// (N, start, end) of the `$N` around the char `offset`
+fn placeholder_at(sql: &str, offset: usize) -> Option<(usize, usize, usize)> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut start = offset.min(chars.len().checked_sub(1)?);
+    while start > 0 && chars[start].is_ascii_digit() {
+        start -= 1;
+    }
+    if chars[start] != '$' {
+        return None;
+    }
+    let end = (start + 1..chars.len())
+        .find(|&i| !chars[i].is_ascii_digit())
+        .unwrap_or(chars.len());
+    let number = chars[start + 1..end]
+        .iter()
+        .collect::<String>()
+        .parse()
+        .ok()?;
+    Some((number, start, end))
+}
+
+/// Rust type that sqlx maps a database type to, as `type_info` is written in `.sqlx`.
+pub fn rust_type(db_name: Option<&str>, type_info: &str) -> String {
+    if let Some(element) = type_info
+        .strip_suffix("Array")
+        .filter(|_| db_name == Some("PostgreSQL"))
+    {
+        return format!("Vec<{}>", rust_type(db_name, element));
+    }
+    let rust_type = match (db_name, type_info.to_ascii_uppercase().as_str()) {
+        (_, "BOOL" | "BOOLEAN") => "bool",
+        (Some("MySQL"), "TINY") => "i8",
+        (_, "INT2" | "SHORT" | "SMALLINT") => "i16",
+        (_, "INT4" | "LONG" | "INT24" | "INT") => "i32",
+        (_, "INT8" | "LONGLONG" | "BIGINT" | "INTEGER" | "INT64") => "i64",
+        (_, "FLOAT4" | "FLOAT") => "f32",
+        (_, "FLOAT8" | "DOUBLE" | "REAL") => "f64",
+        (
+            _,
+            "TEXT" | "VARCHAR" | "BPCHAR" | "CHAR" | "NAME" | "CITEXT" | "VARSTRING" | "STRING",
+        ) => "String",
+        (_, "BYTEA" | "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB") => "Vec<u8>",
+        (_, "UUID") => "uuid::Uuid",
+        (_, "JSON" | "JSONB") => "serde_json::Value",
+        (_, "NUMERIC" | "NEWDECIMAL" | "DECIMAL") => "rust_decimal::Decimal",
+        (_, "DATE") => "chrono::NaiveDate",
+        (_, "TIME") => "chrono::NaiveTime",
+        (_, "TIMESTAMPTZ") | (Some("MySQL"), "TIMESTAMP") => "chrono::DateTime<chrono::Utc>",
+        (_, "TIMESTAMP" | "DATETIME") => "chrono::NaiveDateTime",
+        _ => return type_info.to_string(),
+    };
+    rust_type.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::offline::{link, OfflineCache};
+    use crate::{default_configs, extract_sql_nodes};
+    use pretty_assertions::assert_eq;
+
+    const SOURCE_TXT: &str = r#"
+async fn list_todos(pool: &PgPool, done: bool) {
+    let recs = sqlx::query!("SELECT id, description AS \"description!\" FROM todos WHERE done = $1", done);
+}
+"#;
+
+    fn linked_nodes() -> Vec<SqlNode> {
+        linked_nodes_with(r#"{ "Left": ["Bool"] }"#)
+    }
+
+    fn linked_nodes_with(parameters: &str) -> Vec<SqlNode> {
+        let json = r#"{
+  "db_name": "PostgreSQL",
+  "query": "SELECT id, description AS \"description!\" FROM todos WHERE done = $1",
+  "describe": {
+    "columns": [
+      { "ordinal": 0, "name": "id", "type_info": "Int8" },
+      { "ordinal": 1, "name": "description!", "type_info": "Text" }
+    ],
+    "parameters": PARAMETERS,
+    "nullable": [false, true]
+  }
+}"#
+        .replace("PARAMETERS", parameters);
+        let prepared = PreparedQuery::from_json(".sqlx/query-a.json", &json).unwrap();
+        let mut sql_nodes =
+            extract_sql_nodes(SOURCE_TXT, &default_configs(), Dialect::PostgreSql).unwrap();
+        link(
+            &mut sql_nodes,
+            &OfflineCache {
+                queries: vec![prepared],
+            },
+        );
+        sql_nodes
+    }
+
+    #[test]
+    fn hover_on_macro_path() {
+        let sql_nodes = linked_nodes();
+        let result = hover(
+            &sql_nodes,
+            &Position {
+                line: 2,
+                character: 22,
+            },
+        )
+        .unwrap();
+        assert_eq!(result.range, sql_nodes[0].call.path_range);
+        assert_eq!(
+            result.contents,
+            "```rust\n// columns\nid: i64,\ndescription: String,\n// parameters\n$1: bool,\n```"
+        );
+    }
+
+    #[test]
+    fn hover_on_placeholder() {
+        let sql_nodes = linked_nodes();
+        // `1` of `$1`
+        let position = Position {
+            line: 2,
+            character: 97,
+        };
+        let result = hover(&sql_nodes, &position).unwrap();
+        assert_eq!(
+            result.parameters,
+            vec![HoverParameter {
+                name: "$1".to_string(),
+                sql_type: "Bool".to_string(),
+                rust_type: "bool".to_string(),
+            }]
+        );
+        assert_eq!(
+            result.range,
+            Range {
+                start: Position {
+                    line: 2,
+                    character: 96,
+                },
+                end: Position {
+                    line: 2,
+                    character: 98,
+                },
+            }
+        );
+
+        // outside of any query
+        assert_eq!(hover(&sql_nodes, &Position::default()), None);
+    }
+
+    #[test]
+    fn hover_on_placeholder_missing_from_metadata() {
+        // stale `.sqlx` entry without the parameter
+        let sql_nodes = linked_nodes_with(r#"{ "Left": [] }"#);
+        let result = hover(
+            &sql_nodes,
+            &Position {
+                line: 2,
+                character: 97,
+            },
+        )
+        .unwrap();
+        assert_eq!(result.range, sql_nodes[0].literal.range);
+        assert_eq!(result.parameters, vec![]);
+        assert_eq!(
+            result.contents,
+            "```rust\n// columns\nid: i64,\ndescription: String,\n```"
+        );
+    }
+
+    #[test]
+    fn type_overrides_keep_nullability() {
+        let prepared = PreparedQuery::from_json(
+            ".sqlx/query-b.json",
+            r#"{
+  "db_name": "PostgreSQL",
+  "query": "SELECT id AS \"id?: i64\", done AS \"done!: bool\", tag AS \"tag: Tag\" FROM todos",
+  "describe": {
+    "columns": [
+      { "ordinal": 0, "name": "id?: i64", "type_info": "Int8" },
+      { "ordinal": 1, "name": "done!: bool", "type_info": "Bool" },
+      { "ordinal": 2, "name": "tag: Tag", "type_info": "Text" }
+    ],
+    "parameters": { "Left": [] },
+    "nullable": [false, true, true]
+  }
+}"#,
+        )
+        .unwrap();
+        let columns: Vec<(String, String)> = hover_columns(&prepared)
+            .into_iter()
+            .map(|c| (c.name, c.rust_type))
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                ("id".to_string(), "Option<i64>".to_string()),
+                ("done".to_string(), "bool".to_string()),
+                ("tag".to_string(), "Option<Tag>".to_string()),
+            ]
+        );
+    }
+}
@@ -6,6 +6,9 @@ export const customRawSqlQueryRsSchema = v.array(
     functionName: v.string(),
     sqlArgNo: v.pipe(v.number(), v.minValue(0)),
     isMacro: v.boolean(),
+    dialect: v.optional(
+      v.picklist(["generic", "postgresql", "mysql", "sqlite"]),
+    ),
   }),
 );
 export type CustomRawSqlQueryRs = v.InferOutput<
@@ -15,10 +18,503 @@ export type CustomRawSqlQueryRs = v.InferOutput<
 export async function extractSqlListRs(
   sourceTxt: string,
   configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
 ): Promise<SqlNode[]> {
   const { extract_sql_list } = await import("../pkg");
   return extract_sql_list(
     sourceTxt,
     configs?.map((c) => JSON.stringify(c)),
+    dialect,
   ).map((sqlNode) => JSON.parse(sqlNode));
 }
+
+export type SqlDialectRs = "generic" | "postgresql" | "mysql" | "sqlite";
+
+export type SqlDiagnostic = {
+  range: SqlNode["code_range"];
+  severity: "error" | "warning" | "information" | "hint";
+  code: string;
+  message: string;
+};
+
+export async function checkSqlListRs(
+  sourceTxt: string,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlDiagnostic[]> {
+  const { check_sql_list } = await import("../pkg");
+  return check_sql_list(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((diagnostic) => JSON.parse(diagnostic));
+}
+
+export type SchemaCatalog = {
+  tables: {
+    name: string;
+    columns: {
+      name: string;
+      data_type: string;
+      nullable: boolean;
+      default: string | null;
+    }[];
+    primary_key: string[];
+    indexes: { name: string | null; columns: string[]; unique: boolean }[];
+    foreign_keys: {
+      columns: string[];
+      foreign_table: string;
+      referred_columns: string[];
+    }[];
+  }[];
+};
+
+export async function buildSchemaCatalogRs(
+  migrations: { path: string; sql: string }[],
+  dialect?: SqlDialectRs,
+): Promise<SchemaCatalog> {
+  const { build_schema_catalog } = await import("../pkg");
+  return JSON.parse(
+    build_schema_catalog(
+      migrations.map((m) => JSON.stringify(m)),
+      dialect,
+    ),
+  );
+}
+
+export async function buildDieselSchemaCatalogRs(
+  schemaTxt: string,
+): Promise<SchemaCatalog> {
+  const { build_diesel_schema_catalog } = await import("../pkg");
+  return JSON.parse(build_diesel_schema_catalog(schemaTxt));
+}
+
+export async function checkSqlSchemaRs(
+  sourceTxt: string,
+  catalog: SchemaCatalog,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlDiagnostic[]> {
+  const { check_sql_schema } = await import("../pkg");
+  return check_sql_schema(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    JSON.stringify(catalog),
+  ).map((diagnostic) => JSON.parse(diagnostic));
+}
+
+export async function checkQueryAsListRs(
+  sourceTxt: string,
+  structSources?: string[],
+  catalog?: SchemaCatalog,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlDiagnostic[]> {
+  const { check_query_as_list } = await import("../pkg");
+  return check_query_as_list(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    structSources,
+    catalog ? JSON.stringify(catalog) : undefined,
+  ).map((diagnostic) => JSON.parse(diagnostic));
+}
+
+export type SqlxCacheFile = { path: string; content: string };
+
+export type SqlxPreparedQuery = {
+  path: string;
+  hash: string;
+  db_name: string | null;
+  query: string;
+  parameters: string[];
+  columns: { name: string; type_info: string; nullable: boolean | null }[];
+};
+
+export type SqlFileDiagnostic = SqlDiagnostic & { path: string };
+
+export async function linkOfflineCacheRs(
+  sourceTxt: string,
+  cacheFiles: SqlxCacheFile[],
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<(SqlNode & { offline?: SqlxPreparedQuery })[]> {
+  const { link_offline_cache } = await import("../pkg");
+  return link_offline_cache(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    cacheFiles.map((f) => JSON.stringify(f)),
+  ).map((sqlNode) => JSON.parse(sqlNode));
+}
+
+export async function checkOfflineCacheRs(
+  sourceTxt: string,
+  cacheFiles: SqlxCacheFile[],
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlDiagnostic[]> {
+  const { check_offline_cache } = await import("../pkg");
+  return check_offline_cache(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    cacheFiles.map((f) => JSON.stringify(f)),
+  ).map((diagnostic) => JSON.parse(diagnostic));
+}
+
+export async function findOrphanedCacheEntriesRs(
+  sourceTxts: string[],
+  cacheFiles: SqlxCacheFile[],
+  configs?: CustomRawSqlQueryRs,
+): Promise<SqlFileDiagnostic[]> {
+  const { find_orphaned_cache_entries } = await import("../pkg");
+  return find_orphaned_cache_entries(
+    sourceTxts,
+    configs?.map((c) => JSON.stringify(c)),
+    cacheFiles.map((f) => JSON.stringify(f)),
+  ).map((diagnostic) => JSON.parse(diagnostic));
+}
+
+export type SqlHover = {
+  range: SqlNode["code_range"];
+  columns: { name: string; sql_type: string; rust_type: string }[];
+  parameters: { name: string; sql_type: string; rust_type: string }[];
+  contents: string;
+};
+
+export async function hoverSqlRs(
+  sourceTxt: string,
+  cacheFiles: SqlxCacheFile[],
+  position: { line: number; character: number },
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlHover | null> {
+  const { hover_sql } = await import("../pkg");
+  return JSON.parse(
+    hover_sql(
+      sourceTxt,
+      configs?.map((c) => JSON.stringify(c)),
+      dialect,
+      cacheFiles.map((f) => JSON.stringify(f)),
+      position.line,
+      position.character,
+    ),
+  );
+}
+
+export type SqlReference = {
+  kind: "table" | "column" | "alias" | "cte" | "function";
+  role: "read" | "write" | "ddl";
+  name: string;
+  table: string | null;
+  range: SqlNode["code_range"];
+};
+
+export async function extractSqlReferencesRs(
+  sourceTxt: string,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<(SqlNode & { references?: SqlReference[] })[]> {
+  const { extract_sql_references } = await import("../pkg");
+  return extract_sql_references(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((sqlNode) => JSON.parse(sqlNode));
+}
+
+export type SqlFileEdit = {
+  path: string;
+  edits: { range: SqlNode["code_range"]; new_text: string }[];
+};
+
+export async function renameSqlIdentifierRs(
+  sources: { path: string; content: string }[],
+  target: { table: string; column?: string },
+  newName: string,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlFileEdit[]> {
+  const { rename_sql_identifier } = await import("../pkg");
+  return rename_sql_identifier(
+    sources.map((s) => JSON.stringify(s)),
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    JSON.stringify(target),
+    newName,
+  ).map((fileEdit) => JSON.parse(fileEdit));
+}
+
+export type SqlLocation = { path: string; range: SqlNode["code_range"] };
+
+export type SqlDefinition = {
+  table: string;
+  column: string | null;
+  definition: SqlLocation;
+  alterations: SqlLocation[];
+};
+
+export async function findSqlDefinitionRs(
+  sourceTxt: string,
+  migrations: { path: string; sql: string }[],
+  position: { line: number; character: number },
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlDefinition | null> {
+  const { find_sql_definition } = await import("../pkg");
+  return JSON.parse(
+    find_sql_definition(
+      sourceTxt,
+      configs?.map((c) => JSON.stringify(c)),
+      dialect,
+      migrations.map((m) => JSON.stringify(m)),
+      position.line,
+      position.character,
+    ),
+  );
+}
+
+export type SqlInjection = SqlDiagnostic & {
+  interpolations: SqlNode["code_range"][];
+  suggestion?: string;
+};
+
+/**
+ * Only runtime functions can take a built string, ex) `{ functionName: "query", sqlArgNo: 0, isMacro: false }`.
+ */
+export async function checkSqlInjectionRs(
+  sourceTxt: string,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlInjection[]> {
+  const { check_sql_injection } = await import("../pkg");
+  return check_sql_injection(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((injection) => JSON.parse(injection));
+}
+
+export type SqlCallSite = {
+  function: string;
+  range: SqlNode["code_range"];
+  arg_range: SqlNode["code_range"];
+  content: string | null;
+  dynamic:
+    | "local_variable"
+    | "const_path"
+    | "format"
+    | "function_result"
+    | "unknown"
+    | null;
+};
+
+export async function reportSqlCallSitesRs(
+  sourceTxt: string,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlCallSite[]> {
+  const { report_sql_call_sites } = await import("../pkg");
+  return report_sql_call_sites(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((callSite) => JSON.parse(callSite));
+}
+
+export async function extractSqlFingerprintsRs(
+  sourceTxt: string,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<(SqlNode & { fingerprint?: string })[]> {
+  const { extract_sql_fingerprints } = await import("../pkg");
+  return extract_sql_fingerprints(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((sqlNode) => JSON.parse(sqlNode));
+}
+
+export type SqlDuplicateGroup = {
+  fingerprint: string;
+  normalized: string;
+  locations: SqlLocation[];
+};
+
+export async function findDuplicateQueriesRs(
+  sources: { path: string; content: string }[],
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlDuplicateGroup[]> {
+  const { find_duplicate_queries } = await import("../pkg");
+  return find_duplicate_queries(
+    sources.map((s) => JSON.stringify(s)),
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((group) => JSON.parse(group));
+}
+
+export type SqlStatsMatch = {
+  query: string;
+  stats: Record<string, string>;
+  call_sites: {
+    path: string;
+    line: number;
+    range: SqlNode["code_range"];
+    function: string | null;
+    normalized: string;
+  }[];
+};
+
+export async function joinPgStatsRs(
+  statsCsv: string,
+  sources: { path: string; content: string }[],
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlStatsMatch[]> {
+  const { join_pg_stats } = await import("../pkg");
+  return join_pg_stats(
+    statsCsv,
+    sources.map((s) => JSON.stringify(s)),
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+  ).map((statsMatch) => JSON.parse(statsMatch));
+}
+
+export type SqlTextEdit = SqlFileEdit["edits"][number];
+
+export async function convertSqlPlaceholdersRs(
+  sourceTxt: string,
+  position: { line: number; character: number },
+  style: "dollar" | "question",
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlTextEdit[]> {
+  const { convert_sql_placeholders } = await import("../pkg");
+  return convert_sql_placeholders(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    position.line,
+    position.character,
+    style,
+  ).map((edit) => JSON.parse(edit));
+}
+
+export type SqlFormatOptions = {
+  indent?: string;
+  promoteRawStrings?: boolean;
+  demoteMaxLength?: number | null;
+  layout?: {
+    leadingNewline?: boolean | null;
+    trailingNewline?: boolean | null;
+    bodyIndent?: number;
+    closingAlignment?: "call" | "body";
+  };
+};
+
+export async function formatSqlListRs(
+  sourceTxt: string,
+  options?: SqlFormatOptions,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlTextEdit[]> {
+  const { format_sql_list } = await import("../pkg");
+  return format_sql_list(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    options && JSON.stringify(options),
+  ).map((edit) => JSON.parse(edit));
+}
+
+export type SqlQueryExtraction = {
+  edits: SqlTextEdit[];
+  files: { path: string; content: string }[];
+};
+
+export async function extractSqlQueryRs(
+  sourceTxt: string,
+  position: { line: number; character: number },
+  target: "const" | "file",
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+  // names of the files already in `queries/`
+  queryFiles?: string[],
+): Promise<SqlQueryExtraction | null> {
+  const { extract_sql_query } = await import("../pkg");
+  return JSON.parse(
+    extract_sql_query(
+      sourceTxt,
+      configs?.map((c) => JSON.stringify(c)),
+      dialect,
+      position.line,
+      position.character,
+      target,
+      queryFiles,
+    ),
+  );
+}
+
+export async function convertSqlQueryCallRs(
+  sourceTxt: string,
+  position: { line: number; character: number },
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlTextEdit[]> {
+  const { convert_sql_query_call } = await import("../pkg");
+  return convert_sql_query_call(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    position.line,
+    position.character,
+  ).map((edit) => JSON.parse(edit));
+}
+
+export type SqlLintRule =
+  | "keyword-case"
+  | "no-select-star"
+  | "no-trailing-semicolon"
+  | "insert-column-list";
+
+export type SqlLintConfig = {
+  rules?: Partial<Record<SqlLintRule, "off" | SqlDiagnostic["severity"]>>;
+};
+
+export type SqlLintDiagnostic = SqlDiagnostic & { fix?: SqlTextEdit[] };
+
+export async function lintSqlListRs(
+  sourceTxt: string,
+  lintConfig?: SqlLintConfig,
+  configs?: CustomRawSqlQueryRs,
+  dialect?: SqlDialectRs,
+): Promise<SqlLintDiagnostic[]> {
+  const { lint_sql_list } = await import("../pkg");
+  return lint_sql_list(
+    sourceTxt,
+    configs?.map((c) => JSON.stringify(c)),
+    dialect,
+    lintConfig && JSON.stringify(lintConfig),
+  ).map((diagnostic) => JSON.parse(diagnostic));
+}
+
+export type SqlConfigError = {
+  entry: number;
+  field?: string;
+  message: string;
+};
+
+/**
+ * The other functions skip invalid entries without reporting them.
+ */
+export async function validateSqlConfigsRs(
+  configs: unknown[],
+): Promise<SqlConfigError[]> {
+  const { validate_sql_configs } = await import("../pkg");
+  return validate_sql_configs(configs.map((c) => JSON.stringify(c))).map(
+    (error) => JSON.parse(error),
+  );
+}
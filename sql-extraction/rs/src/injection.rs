@@ -0,0 +1,456 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::dialect::Dialect;
+use crate::edit::text_of;
+use crate::format::encode;
+use crate::literal::{LiteralKind, SqlLiteral};
+use crate::{span_range, DynamicSql, Range};
+use serde::Serialize;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+
+/// SQL built from runtime values, with the values that should be bound instead.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Injection {
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+    /// the interpolated expressions
+    pub interpolations: Vec<Range>,
+    /// the call rewritten with bind parameters, ex) `sqlx::query("... = $1").bind(name)`.
+    /// diesel values are bound as `Text`, to be changed for other SQL types. `None` if the
+    /// dialect is not known, or if a value in a quoted string cannot be concatenated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+enum Part {
+    Sql(String),
+    Value { text: String, range: Range },
+}
+
+/// Report a SQL argument built with `format!`, `concat!` or `String` concatenation of runtime values.
+/// Values that are literals or constants (`SCREAMING_CASE` paths) are not reported.
+pub fn check_injection(source_txt: &str, dynamic_sql: &DynamicSql) -> Option<Injection> {
+    let (builder, parts) = sql_parts(source_txt, &dynamic_sql.expr)?;
+    let values: Vec<(&String, &Range)> = parts
+        .iter()
+        .filter_map(|part| match part {
+            Part::Value { text, range } => Some((text, range)),
+            Part::Sql(_) => None,
+        })
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+
+    let names: Vec<String> = values
+        .iter()
+        .map(|(text, _)| format!("`{}`", text))
+        .collect();
+    Some(Injection {
+        diagnostic: Diagnostic {
+            range: span_range(dynamic_sql.expr.span()),
+            severity: Severity::Warning,
+            code: "sql-injection".to_string(),
+            message: format!(
+                "SQL built with {} from {}, bind them as parameters instead",
+                builder,
+                names.join(", ")
+            ),
+        },
+        interpolations: values.iter().map(|(_, range)| **range).collect(),
+        suggestion: suggestion(dynamic_sql, &parts),
+    })
+}
+
+// (how the SQL is built, parts)
+fn sql_parts(source_txt: &str, expr: &syn::Expr) -> Option<(&'static str, Vec<Part>)> {
+    match expr {
+        syn::Expr::Reference(reference) => sql_parts(source_txt, &reference.expr),
+        syn::Expr::Paren(paren) => sql_parts(source_txt, &paren.expr),
+        // ex) format!(...).as_str()
+        syn::Expr::MethodCall(method_call) if is_conversion(method_call) => {
+            sql_parts(source_txt, &method_call.receiver)
+        }
+        syn::Expr::Macro(expr_macro) => {
+            let name = expr_macro.mac.path.segments.last()?.ident.to_string();
+            let args = expr_macro
+                .mac
+                .parse_body_with(Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated)
+                .ok()?;
+            match name.as_str() {
+                "format" => Some(("`format!`", format_parts(source_txt, &args)?)),
+                "concat" => Some((
+                    "`concat!`",
+                    args.iter().map(|arg| operand(source_txt, arg)).collect(),
+                )),
+                _ => None,
+            }
+        }
+        syn::Expr::Binary(binary) if matches!(binary.op, syn::BinOp::Add(_)) => {
+            let mut operands = Vec::new();
+            flatten_add(expr, &mut operands);
+            Some((
+                "string concatenation",
+                operands.iter().map(|e| operand(source_txt, e)).collect(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn is_conversion(method_call: &syn::ExprMethodCall) -> bool {
+    method_call.args.is_empty()
+        && ["as_str", "as_ref", "to_string", "to_owned", "into", "clone"]
+            .contains(&method_call.method.to_string().as_str())
+}
+
+fn flatten_add<'a>(expr: &'a syn::Expr, operands: &mut Vec<&'a syn::Expr>) {
+    match expr {
+        syn::Expr::Binary(binary) if matches!(binary.op, syn::BinOp::Add(_)) => {
+            flatten_add(&binary.left, operands);
+            flatten_add(&binary.right, operands);
+        }
+        _ => operands.push(expr),
+    }
+}
+
+// ex) "SELECT ...".to_string(), String::from("..."), &name
+fn operand(source_txt: &str, expr: &syn::Expr) -> Part {
+    match literal_text(expr) {
+        Some(sql) => Part::Sql(sql),
+        None => value(source_txt, expr),
+    }
+}
+
+fn literal_text(expr: &syn::Expr) -> Option<String> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+            syn::Lit::Str(s) => Some(s.value()),
+            syn::Lit::Int(i) => Some(i.base10_digits().to_string()),
+            syn::Lit::Float(f) => Some(f.base10_digits().to_string()),
+            syn::Lit::Bool(b) => Some(b.value.to_string()),
+            _ => None,
+        },
+        syn::Expr::Reference(reference) => literal_text(&reference.expr),
+        syn::Expr::Paren(paren) => literal_text(&paren.expr),
+        syn::Expr::MethodCall(method_call) if is_conversion(method_call) => {
+            literal_text(&method_call.receiver)
+        }
+        // String::from("...")
+        syn::Expr::Call(call) if call.args.len() == 1 => match &*call.func {
+            syn::Expr::Path(path)
+                if path.path.segments.last().is_some_and(|s| s.ident == "from") =>
+            {
+                literal_text(&call.args[0])
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn value(source_txt: &str, expr: &syn::Expr) -> Part {
+    let range = span_range(expr.span());
+    if is_constant(expr) {
+        return Part::Sql(format!("{{{}}}", text_of(source_txt, &range)));
+    }
+    Part::Value {
+        text: text_of(source_txt, &range),
+        range,
+    }
+}
+
+// ex) TABLE_NAME, schema::TABLE
+fn is_constant(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Path(path) => path.path.segments.last().is_some_and(|s| {
+            let ident = s.ident.to_string();
+            ident.chars().any(|c| c.is_ascii_uppercase())
+                && !ident.chars().any(|c| c.is_ascii_lowercase())
+        }),
+        syn::Expr::Reference(reference) => is_constant(&reference.expr),
+        _ => false,
+    }
+}
+
+// https://doc.rust-lang.org/std/fmt/#syntax
+fn format_parts(
+    source_txt: &str,
+    args: &Punctuated<syn::Expr, syn::Token![,]>,
+) -> Option<Vec<Part>> {
+    let mut args = args.iter();
+    let format_str = match args.next()? {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(lit),
+            ..
+        }) => lit,
+        _ => return None,
+    };
+    let literal = SqlLiteral::parse(
+        &format_str.token().to_string(),
+        span_range(format_str.span()).start,
+    )?;
+    let (mut positional, mut named) = (Vec::new(), Vec::new());
+    for arg in args {
+        match arg {
+            // name = expr
+            syn::Expr::Assign(assign) => match &*assign.left {
+                syn::Expr::Path(path) if path.path.get_ident().is_some() => {
+                    named.push((path.path.get_ident().unwrap().to_string(), &*assign.right))
+                }
+                _ => return None,
+            },
+            _ => positional.push(arg),
+        }
+    }
+
+    let chars: Vec<char> = literal.value.chars().collect();
+    let mut parts = Vec::new();
+    let mut sql = String::new();
+    let mut next_positional = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        match (chars[i], chars.get(i + 1)) {
+            ('{', Some('{')) | ('}', Some('}')) => {
+                sql.push(chars[i]);
+                i += 2;
+            }
+            ('{', _) => {
+                let end = i + chars[i..].iter().position(|c| *c == '}')?;
+                let spec: String = chars[i + 1..end].iter().collect();
+                let name = spec.split(':').next().unwrap_or_default().trim();
+                let part = if name.is_empty() {
+                    next_positional += 1;
+                    value(source_txt, positional.get(next_positional - 1)?)
+                } else if let Ok(index) = name.parse::<usize>() {
+                    value(source_txt, positional.get(index)?)
+                } else if let Some((_, expr)) = named.iter().find(|(n, _)| n == name) {
+                    value(source_txt, expr)
+                } else {
+                    // captured identifier, ex) "{name}"
+                    Part::Value {
+                        text: name.to_string(),
+                        range: literal.range_of(i, end + 1),
+                    }
+                };
+                match part {
+                    Part::Sql(text) => sql.push_str(&text),
+                    part => {
+                        parts.push(Part::Sql(std::mem::take(&mut sql)));
+                        parts.push(part);
+                    }
+                }
+                i = end + 1;
+            }
+            (c, _) => {
+                sql.push(c);
+                i += 1;
+            }
+        }
+    }
+    parts.push(Part::Sql(sql));
+    Some(parts)
+}
+
+// `None` when the placeholder style of the dialect is unknown, or when a value inside a
+// quoted string cannot be concatenated, ex) `'%{}%'` in MySQL where `||` is `OR`
+fn suggestion(dynamic_sql: &DynamicSql, parts: &[Part]) -> Option<String> {
+    // `diesel::sql_query(...).bind::<Text, _>(name)`, diesel cannot infer the SQL type
+    let bind = match dynamic_sql.call.function_name() {
+        "sql_query" => ".bind::<diesel::sql_types::Text, _>",
+        _ => ".bind",
+    };
+    let mut sql = String::new();
+    let mut binds = Vec::new();
+    // quotes of the SQL as written, a value after an odd number of them is in a string
+    let mut quotes = 0;
+    // the previous value was in a string that goes on after it
+    let mut in_string = false;
+    for part in parts {
+        match part {
+            Part::Sql(text) => {
+                quotes += text.matches('\'').count();
+                let mut text = text.as_str();
+                if in_string {
+                    // '{}' -> $1, '{}%' -> $1 || '%'
+                    match text
+                        .strip_prefix('\'')
+                        .filter(|rest| !rest.starts_with('\''))
+                    {
+                        Some(rest) => text = rest,
+                        None => sql.push_str(" || '"),
+                    }
+                }
+                sql.push_str(text);
+            }
+            Part::Value { text, .. } => {
+                in_string = quotes % 2 == 1;
+                if in_string {
+                    match sql.strip_suffix('\'').filter(|rest| !rest.ends_with('\'')) {
+                        Some(rest) => sql.truncate(rest.len()),
+                        None => sql.push_str("' || "),
+                    }
+                }
+                binds.push(format!("{}({})", bind, text));
+                match dynamic_sql.dialect {
+                    Dialect::PostgreSql => sql.push_str(&format!("${}", binds.len())),
+                    Dialect::MySql | Dialect::Sqlite => sql.push('?'),
+                    Dialect::Generic => return None,
+                }
+            }
+        }
+    }
+    if sql.contains(" || ") && dynamic_sql.dialect == Dialect::MySql {
+        return None;
+    }
+
+    let kind = if sql.contains('"') || sql.contains('\n') {
+        LiteralKind::RawStr { hashes: 1 }
+    } else {
+        LiteralKind::Str
+    };
+    Some(format!(
+        "{}({}){}",
+        dynamic_sql.call.path.join("::"),
+        encode(kind, &sql),
+        binds.join("")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{extract_dynamic_sql, Config, Position};
+    use pretty_assertions::assert_eq;
+
+    fn check(source_txt: &str) -> Vec<Injection> {
+        let configs = vec![
+            Config {
+                functionName: "query".to_string(),
+                sqlArgNo: 0,
+                isMacro: false,
+                dialect: None,
+            },
+            Config {
+                functionName: "sql_query".to_string(),
+                sqlArgNo: 0,
+                isMacro: false,
+                dialect: None,
+            },
+        ];
+        extract_dynamic_sql(source_txt, &configs, Dialect::PostgreSql)
+            .unwrap()
+            .iter()
+            .filter_map(|dynamic_sql| check_injection(source_txt, dynamic_sql))
+            .collect()
+    }
+
+    #[test]
+    fn format_with_user_value() {
+        let result = check(
+            r#"
+async fn find(pool: &PgPool, name: &str) {
+    sqlx::query(&format!("SELECT id FROM users WHERE name = '{}' AND role = '{role}'", name));
+}
+"#,
+        );
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].diagnostic.code, "sql-injection");
+        assert_eq!(
+            result[0].diagnostic.message,
+            "SQL built with `format!` from `name`, `role`, bind them as parameters instead"
+        );
+        assert_eq!(
+            result[0].interpolations,
+            vec![
+                // `name`
+                Range {
+                    start: Position {
+                        line: 2,
+                        character: 87,
+                    },
+                    end: Position {
+                        line: 2,
+                        character: 91,
+                    },
+                },
+                // `{role}` in the format string
+                Range {
+                    start: Position {
+                        line: 2,
+                        character: 77,
+                    },
+                    end: Position {
+                        line: 2,
+                        character: 83,
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            result[0].suggestion.as_deref(),
+            Some(
+                r#"sqlx::query("SELECT id FROM users WHERE name = $1 AND role = $2").bind(name).bind(role)"#
+            )
+        );
+    }
+
+    #[test]
+    fn concatenation_and_constants() {
+        let result = check(
+            r#"
+const TABLE: &str = "users";
+
+fn find(conn: &mut PgConnection, name: String) {
+    diesel::sql_query("SELECT id FROM users WHERE name = '".to_string() + &name + "'");
+    diesel::sql_query(format!("SELECT id FROM {}", TABLE));
+    diesel::sql_query(query_text);
+}
+"#,
+        );
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].suggestion.as_deref(),
+            Some(
+                r#"diesel::sql_query("SELECT id FROM users WHERE name = $1").bind::<diesel::sql_types::Text, _>(&name)"#
+            )
+        );
+    }
+
+    #[test]
+    fn values_inside_quoted_strings() {
+        let result = check(
+            r###"
+fn search(pool: &PgPool, q: &str, tag: &str) {
+    sqlx::query(&format!("SELECT id FROM todos WHERE title LIKE '%{}%' AND note = 'it''{}'", q, q));
+    sqlx::query(&format!(r##"SELECT id FROM todos WHERE "#tag" = '{}'"##, tag));
+}
+"###,
+        );
+        assert_eq!(
+            result[0].suggestion.as_deref(),
+            Some(
+                r#"sqlx::query("SELECT id FROM todos WHERE title LIKE '%' || $1 || '%' AND note = 'it''' || $2").bind(q).bind(q)"#
+            )
+        );
+        // enough `#` for the `"#` of the SQL
+        assert_eq!(
+            result[1].suggestion.as_deref(),
+            Some(r###"sqlx::query(r##"SELECT id FROM todos WHERE "#tag" = $1"##).bind(tag)"###)
+        );
+
+        // `||` is `OR` in MySQL, and the placeholder style of an unknown dialect is unknown
+        let source_txt = r#"fn f() { sqlx::query(&format!("SELECT '%{}'", q)); }"#;
+        let configs = vec![Config {
+            functionName: "query".to_string(),
+            sqlArgNo: 0,
+            isMacro: false,
+            dialect: None,
+        }];
+        for dialect in [Dialect::MySql, Dialect::Generic] {
+            let dynamic_sql = &extract_dynamic_sql(source_txt, &configs, dialect).unwrap()[0];
+            let injection = check_injection(source_txt, dynamic_sql).unwrap();
+            assert_eq!(injection.suggestion, None);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use crate::SqlNode;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Configured layout of multi-line SQL in a literal. Unset newlines follow the literal as written.
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct LayoutStyle {
+    /// start the SQL on the line after the opening delimiter
+    pub leading_newline: Option<bool>,
+    /// put the closing delimiter on its own line
+    pub trailing_newline: Option<bool>,
+    /// indentation levels of the SQL, relative to the line the literal starts on
+    pub body_indent: usize,
+    pub closing_alignment: ClosingAlignment,
+}
+
+impl Default for LayoutStyle {
+    fn default() -> Self {
+        LayoutStyle {
+            leading_newline: None,
+            trailing_newline: None,
+            body_indent: 1,
+            closing_alignment: ClosingAlignment::Call,
+        }
+    }
+}
+
+/// Where a closing delimiter on its own line is indented to.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ClosingAlignment {
+    /// the indentation of the line the literal starts on, usually the call
+    #[default]
+    Call,
+    /// the indentation of the SQL
+    Body,
+}
+
+/// Layout of the SQL of one literal, resolved from a style and the call site.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiteralLayout {
+    pub leading_newline: bool,
+    /// before each line of SQL but the first one when it is on the opening line
+    pub body_indent: String,
+    pub trailing_newline: bool,
+    /// before the closing delimiter when it is on its own line
+    pub closing_indent: String,
+}
+
+impl LiteralLayout {
+    /// Resolve `style` for a node: indentation from the line its literal starts on, and the
+    /// newlines it does not set from the literal as written. The style only adds or removes
+    /// newlines around SQL of several lines, a one-line query keeps its own.
+    pub fn derive(
+        source_txt: &str,
+        sql_node: &SqlNode,
+        style: &LayoutStyle,
+        indent: &str,
+        multi_line: bool,
+    ) -> LiteralLayout {
+        let value = &sql_node.literal.value;
+        let written_leading = value[..value.len() - value.trim_start().len()].contains('\n');
+        let written_trailing = value[value.trim_end().len()..].contains('\n');
+        let line_indent = line_indent(source_txt, sql_node.literal.range.start.line);
+        let body_indent = format!("{}{}", line_indent, indent.repeat(style.body_indent));
+        let (leading_newline, trailing_newline) = match multi_line {
+            true => (
+                style.leading_newline.unwrap_or(written_leading),
+                style.trailing_newline.unwrap_or(written_trailing),
+            ),
+            false => (written_leading, written_trailing),
+        };
+        LiteralLayout {
+            leading_newline,
+            trailing_newline,
+            closing_indent: match style.closing_alignment {
+                ClosingAlignment::Call => line_indent,
+                ClosingAlignment::Body => body_indent.clone(),
+            },
+            body_indent,
+        }
+    }
+
+    /// Literal value holding the lines of formatted SQL in this layout. Only the lines are
+    /// indented, not the line breaks within a line, ex) in a string constant.
+    pub fn apply(&self, lines: &[impl AsRef<str>]) -> String {
+        let body = lines
+            .iter()
+            .map(AsRef::as_ref)
+            .enumerate()
+            .map(|(i, line)| {
+                if line.is_empty() || (i == 0 && !self.leading_newline) {
+                    line.to_string()
+                } else {
+                    format!("{}{}", self.body_indent, line)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        format!(
+            "{}{}{}",
+            if self.leading_newline { "\n" } else { "" },
+            body,
+            if self.trailing_newline {
+                format!("\n{}", self.closing_indent)
+            } else {
+                String::new()
+            }
+        )
+    }
+}
+
+fn line_indent(source_txt: &str, line: usize) -> String {
+    source_txt
+        .split('\n')
+        .nth(line)
+        .unwrap_or_default()
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{default_configs, extract_sql_nodes};
+    use pretty_assertions::assert_eq;
+
+    const SOURCE_TXT: &str = r##"
+async fn list(pool: &PgPool) {
+    let todos = sqlx::query!(
+        r#"
+            SELECT id
+            FROM todos
+                "#
+    );
+}
+"##;
+
+    fn derive(style: &LayoutStyle) -> LiteralLayout {
+        let sql_nodes =
+            extract_sql_nodes(SOURCE_TXT, &default_configs(), Dialect::PostgreSql).unwrap();
+        LiteralLayout::derive(SOURCE_TXT, &sql_nodes[0], style, "    ", true)
+    }
+
+    #[test]
+    fn derive_from_call_site() {
+        // the literal starts its own line, indented by 8
+        let layout = derive(&LayoutStyle::default());
+        assert_eq!(
+            layout,
+            LiteralLayout {
+                leading_newline: true,
+                body_indent: " ".repeat(12),
+                trailing_newline: true,
+                closing_indent: " ".repeat(8),
+            }
+        );
+        assert_eq!(
+            layout.apply(&["SELECT", "    id", "FROM", "    todos"]),
+            "\n            SELECT\n                id\n            FROM\n                todos\n        "
+        );
+    }
+
+    #[test]
+    fn configured_style() {
+        let layout = derive(&LayoutStyle {
+            leading_newline: Some(false),
+            trailing_newline: Some(false),
+            body_indent: 2,
+            closing_alignment: ClosingAlignment::Body,
+        });
+        assert_eq!(
+            layout.apply(&["SELECT", "    id", "FROM", "    todos"]),
+            "SELECT\n                    id\n                FROM\n                    todos"
+        );
+        let layout = derive(&LayoutStyle {
+            closing_alignment: ClosingAlignment::Body,
+            ..Default::default()
+        });
+        assert_eq!(
+            layout.apply(&["SELECT 1"]),
+            "\n            SELECT 1\n            "
+        );
+    }
+}
@@ -1,4 +1,39 @@
-use proc_macro2::TokenTree;
+pub mod audit;
+pub mod convert_call;
+pub mod definition;
+pub mod diagnostic;
+pub mod dialect;
+pub mod edit;
+pub mod extract_query;
+pub mod fingerprint;
+pub mod format;
+pub mod hover;
+pub mod injection;
+pub mod layout;
+pub mod lint;
+pub mod literal;
+pub mod offline;
+pub mod pg_stats;
+pub mod placeholder;
+pub mod project;
+pub mod query_as;
+pub mod references;
+pub mod rename;
+pub mod schema;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod syntax;
+pub mod validation;
+pub mod workspace;
+
+use diagnostic::SerializedDiagnosticList;
+use dialect::Dialect;
+use literal::SqlLiteral;
+use proc_macro2::{TokenStream, TokenTree};
+use quote::ToTokens;
+use schema::migrations::Migration;
+use schema::Catalog;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use syn::spanned::Spanned;
 use syn::visit::{self, Visit};
@@ -23,149 +58,314 @@ macro_rules! function {
     }};
 }
 
-#[derive(Serialize, Debug)]
-struct Position {
-    line: usize,      // 0-indexed
-    character: usize, // 0-indexed
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,      // 0-indexed
+    pub character: usize, // 0-indexed
+}
+
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Range {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Range {
+    pub fn contains(&self, position: &Position) -> bool {
+        self.start <= *position && *position <= self.end
+    }
+}
+
+pub(crate) fn span_range(span: proc_macro2::Span) -> Range {
+    Range {
+        start: Position {
+            line: span.start().line - 1, // -1 for 1-indexed to 0-indexed
+            character: span.start().column,
+        },
+        end: Position {
+            line: span.end().line - 1,
+            character: span.end().column,
+        },
+    }
+}
+
+/// The macro invocation or function call that a SqlNode was extracted from.
+#[derive(Debug, Clone, Default)]
+pub struct CallSite {
+    /// ex) ["sqlx", "query_as"]
+    pub path: Vec<String>,
+    pub is_macro: bool,
+    /// range of the path, `!` included for macros
+    pub path_range: Range,
+    /// range of the whole call, arguments included
+    pub range: Range,
+    pub args: Vec<Argument>,
+    pub sql_arg_no: usize,
+    /// innermost enclosing function, ex) "list_todos", "TodoRepository::list"
+    pub enclosing_fn: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Argument {
+    pub text: String, // tokens as printed by proc_macro2, ex) "models :: Todo"
+    pub range: Range,
 }
 
-#[derive(Serialize, Debug)]
-struct Range {
-    start: Position,
-    end: Position,
+impl CallSite {
+    /// ex) "query_as"
+    pub fn function_name(&self) -> &str {
+        self.path.last().map(String::as_str).unwrap_or_default()
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct SqlNode {
+    pub code_range: Range,
+    pub content: String,
+    pub method_line: usize, // 0-indexed
+    pub dialect: Dialect,
+    /// decoded literal with its source map, not serialized
+    #[serde(skip)]
+    pub literal: SqlLiteral,
+    #[serde(skip)]
+    pub call: CallSite,
+    /// sqlx offline metadata, see `offline::link`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offline: Option<offline::PreparedQuery>,
+    /// tables, columns, ... of the query, see `references::link`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<references::Reference>,
+    /// hash of the normalized SQL, see `fingerprint::link`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
 }
 
-#[derive(Serialize, Debug)]
-struct SqlNode {
-    code_range: Range,
-    content: String,
-    method_line: usize, // 0-indexed
+/// A configured call whose SQL argument is not a string literal, ex) `sqlx::query(&format!(...))`.
+#[derive(Debug, Clone)]
+pub struct DynamicSql {
+    pub call: CallSite,
+    pub dialect: Dialect,
+    /// the SQL argument
+    pub expr: syn::Expr,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// A function or macro whose argument is SQL.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
 #[allow(non_snake_case)]
-struct Config {
-    functionName: String,
-    sqlArgNo: usize, // 0-indexed, first argument is 0
-    isMacro: bool,
+pub struct Config {
+    /// last segment of the path, ex) "query_as"
+    pub functionName: String,
+    /// 0-indexed, first argument is 0
+    pub sqlArgNo: usize,
+    /// `query!(...)` rather than `query(...)`
+    pub isMacro: bool,
+    /// used when the dialect cannot be inferred from the surrounding code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dialect: Option<Dialect>,
+}
+
+/// sqlx macros, used when no config is given
+pub fn default_configs() -> Vec<Config> {
+    vec![
+        Config {
+            functionName: "query".to_string(),
+            sqlArgNo: 0,
+            isMacro: true,
+            dialect: None,
+        },
+        Config {
+            functionName: "query_as".to_string(),
+            sqlArgNo: 1,
+            isMacro: true,
+            dialect: None,
+        },
+    ]
 }
 
 type SerializedSqlNodeList = Vec<String>;
 
 #[derive(Clone)]
 struct QueryVisitor {
-    sql_node_list: SerializedSqlNodeList,
+    sql_node_list: Vec<SqlNode>,
+    dynamic_sql_list: Vec<DynamicSql>,
     configs: Vec<Config>,
+    default_dialect: Dialect,
+    /// dialects inferred from enclosing fn/closure parameters and block locals, innermost last
+    dialect_scopes: Vec<Option<Dialect>>,
+    /// enclosing functions, innermost last
+    fn_names: Vec<String>,
+    /// self types of enclosing impl and trait blocks, innermost last
+    impl_types: Vec<String>,
+}
+
+impl QueryVisitor {
+    /// Resolve the dialect of a query: call-site backend argument, enclosing scopes, config, default.
+    fn resolve_dialect(&self, call_dialect: Option<Dialect>, config: &Config) -> Dialect {
+        call_dialect
+            .or_else(|| self.dialect_scopes.iter().rev().find_map(|d| *d))
+            .or(config.dialect)
+            .unwrap_or(self.default_dialect)
+    }
+
+    // ex) "TodoRepository::list"
+    fn method_name(&self, ident: &syn::Ident) -> String {
+        match self.impl_types.last().filter(|t| !t.is_empty()) {
+            Some(self_ty) => format!("{}::{}", self_ty, ident),
+            None => ident.to_string(),
+        }
+    }
+
+    fn with_dialect_scope<'a>(
+        &mut self,
+        inputs: impl Iterator<Item = &'a syn::Type>,
+        visit: impl FnOnce(&mut Self),
+    ) {
+        let dialect = inputs.into_iter().find_map(dialect::infer_from_type);
+        self.dialect_scopes.push(dialect);
+        visit(self);
+        self.dialect_scopes.pop();
+    }
+
+    /// Build a SqlNode from a string literal token.
+    /// `lit_token` is the literal as written in the source, e.g. `r#"SELECT 1"#`.
+    fn push_sql_node(
+        &mut self,
+        lit_token: &str,
+        lit_span: proc_macro2::Span,
+        path_segment: &syn::PathSegment,
+        dialect: Dialect,
+        call: CallSite,
+    ) {
+        let lit_start = Position {
+            line: lit_span.start().line - 1,    // -1 for 1-indexed to 0-indexed
+            character: lit_span.start().column, // column is 0-indexed
+        };
+        // only string literals can hold SQL
+        let literal = match SqlLiteral::parse(lit_token, lit_start) {
+            Some(literal) => literal,
+            None => return,
+        };
+
+        let mut sql_lit = lit_token.to_string();
+        let mut start = lit_start;
+        let mut end = Position {
+            line: lit_span.end().line - 1,    // -1 for 1-indexed to 0-indexed
+            character: lit_span.end().column, // column is 0-indexed
+        };
+
+        // If query is surrounded by "" or r#""# then remove it
+        if sql_lit.starts_with("r#\"") {
+            sql_lit = sql_lit
+                .trim_start_matches("r#\"")
+                .trim_end_matches("\"#")
+                .to_string();
+
+            // remove 'r#""#'
+            // adjust position and if "\n" is included in the sql_lit, then add "\n" length to start line
+            let r_sharp_quote_len = "r#\"".len();
+            let sharp_quote_len = "\"#".len();
+            start.character += r_sharp_quote_len - 1 // -1 for 1-indexed to 0-indexed
+                + if sql_lit.contains('\n') {
+                    "\n".len()
+                } else {
+                    0
+                };
+            end.character -= sharp_quote_len;
+        } else if sql_lit.starts_with("\"") {
+            sql_lit = sql_lit
+                .trim_start_matches("\"")
+                .trim_end_matches("\"")
+                .to_string();
+
+            // remove '""'
+            start.character += 1;
+            end.character -= 1;
+        }
+
+        let sql_node = SqlNode {
+            code_range: Range { start, end },
+            content: sql_lit,
+            method_line: path_segment.span().start().line - 1, // -1 for 1-indexed to 0-indexed
+            dialect,
+            literal,
+            call,
+            offline: None,
+            references: vec![],
+            fingerprint: None,
+        };
+
+        self.sql_node_list.push(sql_node);
+    }
 }
 
 // NOTE: re-instructed to use the syn crate
 impl<'ast> Visit<'ast> for QueryVisitor {
     // visit sqlx macro
     fn visit_macro(&mut self, mac: &'ast syn::Macro) {
-        for config in &self.configs {
+        for config in self.configs.clone() {
             if !config.isMacro {
-                let mut clone_self = self.clone();
-                return visit::visit_macro(&mut clone_self, mac);
+                continue;
             }
             // path_segment ex) sqlx::query!
             // path_segments[0]: sqlx
             // path_segments[1]: query!
             for path_segment in &mac.path.segments {
-                let mut sql_lit = String::new();
-                let mut sql_token: Option<TokenTree> = None;
-                if path_segment.ident == config.functionName {
-                    for (i, token) in mac.tokens.clone().into_iter().enumerate() {
-                        // if arguments: "INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id", description
-                        // tokens[0]: "INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id" (arg1 == i / 2 + 1)
-                        // tokens[1]: ,
-                        // tokens[2]: description (arg2 == i / 2 + 1)
-                        #[cfg(debug_assertions)]
-                        println!("{} config: {:?}, argNo: {}", function!(), config, i / 2 + 1);
-                        println!("{} token[{}]: {:?}", function!(), i, token.to_string());
-                    if i / 2 == config.sqlArgNo {
-                        sql_token = Some(token);
-                        break;
-                    }
-                    }
+                if path_segment.ident != config.functionName {
+                    continue;
                 }
-
-                // get only Literal(Literal) from TokenTree
-                let lit = match sql_token {
-                    Some(proc_macro2::TokenTree::Literal(lit)) => lit,
-                    _ => continue,
-                };
-                #[cfg(debug_assertions)]
-                println!(
-                    "{} path_segment line: {:?}",
-                    function!(),
-                    path_segment.span().start().line
-                );
-                println!("{} lit: {:?}", function!(), lit);
-                println!("{} lit span start: {:?}", function!(), lit.span().start());
-                println!("{} lit span end: {:?}", function!(), lit.span().end());
-
-                let mut start = Position {
-                    line: lit.span().start().line - 1, // -1 for 1-indexed to 0-indexed
-                    character: lit.span().start().column, // column is 0-indexed
-                };
-                let mut end = Position {
-                    line: lit.span().end().line - 1,    // -1 for 1-indexed to 0-indexed
-                    character: lit.span().end().column, // column is 0-indexed
+                // if arguments: Todo, "SELECT id FROM todos WHERE id = $1", id
+                // args[0]: Todo
+                // args[1]: "SELECT id FROM todos WHERE id = $1"
+                // args[2]: id
+                let args = macro_args(mac.tokens.clone());
+                let sql_arg = match args.get(config.sqlArgNo) {
+                    Some(sql_arg) => sql_arg.clone(),
+                    None => continue,
                 };
-
-                // concat lit
-                sql_lit.push_str(&lit.to_string());
-                // If query is surrounded by "" or r#""# then remove it
-                if sql_lit.starts_with("r#\"") {
-                    sql_lit = sql_lit
-                        .trim_start_matches("r#\"")
-                        .trim_end_matches("\"#")
-                        .to_string();
-
-                    // remove 'r#""#'
-                    // adjust position and if "\n" is included in the sql_lit, then add "\n" length to start line
-                    let r_sharp_quote_len = "r#\"".len();
-                    let sharp_quote_len = "\"#".len();
-                    start.character += r_sharp_quote_len - 1 // -1 for 1-indexed to 0-indexed
-                        + if let Some(_) = sql_lit.find("\n") {
-                            "\n".len()
-                        } else {
-                            0
-                        };
-                    end.character -= sharp_quote_len;
-                } else if sql_lit.starts_with("\"") {
-                    sql_lit = sql_lit
-                        .trim_start_matches("\"")
-                        .trim_end_matches("\"")
-                        .to_string();
-
-                    // remove '""'
-                    start.character += 1;
-                    end.character -= 1;
-                }
-
-                let sql_node = SqlNode {
-                    code_range: Range {
-                        start: Position {
-                            line: start.line,
-                            character: start.character,
-                        },
-                        end: Position {
-                            line: end.line,
-                            character: end.character,
-                        },
+                let call = CallSite {
+                    path: path_idents(&mac.path),
+                    is_macro: true,
+                    path_range: Range {
+                        start: span_range(mac.path.span()).start,
+                        end: span_range(mac.bang_token.span()).end,
                     },
-                    content: sql_lit.clone(),
-                    method_line: path_segment.span().start().line - 1, // -1 for 1-indexed to 0-indexed
+                    range: span_range(mac.span()),
+                    args: args
+                        .iter()
+                        .map(|tokens| Argument {
+                            text: TokenStream::from_iter(tokens.clone()).to_string(),
+                            range: Range {
+                                start: span_range(tokens[0].span()).start,
+                                end: span_range(tokens[tokens.len() - 1].span()).end,
+                            },
+                        })
+                        .collect(),
+                    sql_arg_no: config.sqlArgNo,
+                    enclosing_fn: self.fn_names.last().cloned(),
                 };
+                let dialect = self.resolve_dialect(None, &config);
 
-                #[cfg(debug_assertions)]
-                println!("{} lit_str: {}", function!(), sql_lit);
-                println!("{} sql_node: {:?}", function!(), sql_node);
-
-                // serialize sql_node to json and push
-                let sql_node_str = serde_json::to_string(&sql_node).unwrap();
-                self.sql_node_list.push(sql_node_str);
+                // get only Literal(Literal) from TokenTree
+                match sql_arg.as_slice() {
+                    [TokenTree::Literal(lit)] => self.push_sql_node(
+                        &lit.to_string(),
+                        lit.span(),
+                        path_segment,
+                        dialect,
+                        call,
+                    ),
+                    _ => {
+                        let tokens = TokenStream::from_iter(sql_arg);
+                        self.dynamic_sql_list.push(DynamicSql {
+                            call,
+                            dialect,
+                            // keep call sites whose argument is not an expression, ex) a macro fragment
+                            expr: syn::parse2(tokens.clone())
+                                .unwrap_or(syn::Expr::Verbatim(tokens)),
+                        });
+                    }
+                }
             }
         }
 
@@ -174,8 +374,7 @@ impl<'ast> Visit<'ast> for QueryVisitor {
     }
 
     fn visit_expr_call(&mut self, expr_call: &'ast syn::ExprCall) {
-        println!("Found expr call {:?}", expr_call.func.span().start());
-        for config in &self.configs {
+        for config in self.configs.clone() {
             if config.isMacro {
                 continue;
             }
@@ -186,150 +385,882 @@ impl<'ast> Visit<'ast> for QueryVisitor {
                 _ => return visit::visit_expr_call(self, expr_call),
             };
             for path_segment in &path.segments {
-                println!("path_segment: {:?}", path_segment.ident);
                 if path_segment.ident != config.functionName {
                     continue;
                 }
 
-                let mut sql_lit = String::new();
-                let mut start: Position = Position {
-                    line: 0,
-                    character: 0,
+                let sql_arg = match expr_call.args.iter().nth(config.sqlArgNo) {
+                    Some(sql_arg) => sql_arg,
+                    None => break,
                 };
-                let mut end: Position = Position {
-                    line: 0,
-                    character: 0,
-                };
-                println!("path_segment.indent: {}", path_segment.ident);
-                println!("path_segment.args: {}", path_segment.ident);
-                for (i, arg) in expr_call.args.clone().into_iter().enumerate() {
-                    let expr_lit = match &arg {
-                        syn::Expr::Lit(syn::ExprLit { lit, .. }) => lit,
-                        _ => return visit::visit_expr_call(self, expr_call),
-                    };
-                    let lit = match expr_lit {
-                        syn::Lit::Str(lit) => lit,
-                        _ => return visit::visit_expr_call(self, expr_call),
-                    };
-                    println!("expr_lit: {:#?}", lit.span().start());
-                    println!("expr_lit: {:#?}", lit.span().end());
-                    if i == config.sqlArgNo {
-                        sql_lit = lit.token().to_string();
-                        start = Position {
-                            line: lit.span().start().line - 1, // -1 for 1-indexed to 0-indexed
-                            character: lit.span().start().column, // column is 0-indexed
-                        };
-                        end = Position {
-                            line: lit.span().end().line - 1,    // -1 for 1-indexed to 0-indexed
-                            character: lit.span().end().column, // column is 0-indexed
-                        };
-                        break;
-                    }
-                }
-                println!("sql_lit: {}", sql_lit);
-
-                // If query is surrounded by "" or r#""# then remove it
-                if sql_lit.starts_with("r#\"") {
-                    sql_lit = sql_lit
-                        .trim_start_matches("r#\"")
-                        .trim_end_matches("\"#")
-                        .to_string();
-
-                    // remove 'r#""#'
-                    // adjust position and if "\n" is included in the sql_lit, then add "\n" length to start line
-                    let r_sharp_quote_len = "r#\"".len();
-                    let sharp_quote_len = "\"#".len();
-                    start.character += r_sharp_quote_len - 1 // -1 for 1-indexed to 0-indexed
-                        + if let Some(_) = sql_lit.find("\n") {
-                            "\n".len()
-                        } else {
-                            0
-                        };
-                    end.character -= sharp_quote_len;
-                } else if sql_lit.starts_with("\"") {
-                    sql_lit = sql_lit
-                        .trim_start_matches("\"")
-                        .trim_end_matches("\"")
-                        .to_string();
-
-                    // remove '""'
-                    start.character += 1;
-                    end.character -= 1;
-                }
 
-                let sql_node = SqlNode {
-                    code_range: Range {
-                        start: Position {
-                            line: start.line,
-                            character: start.character,
-                        },
-                        end: Position {
-                            line: end.line,
-                            character: end.character,
-                        },
-                    },
-                    content: sql_lit.clone(),
-                    method_line: path_segment.span().start().line - 1, // -1 for 1-indexed to 0-indexed
+                // ex) Statement::from_sql_and_values(DbBackend::Postgres, "SELECT ...", [])
+                let call_dialect = expr_call.args.iter().find_map(dialect::infer_from_expr);
+                let dialect = self.resolve_dialect(call_dialect, &config);
+                let call = CallSite {
+                    path: path_idents(path),
+                    is_macro: false,
+                    path_range: span_range(expr_call.func.span()),
+                    range: span_range(expr_call.span()),
+                    args: expr_call
+                        .args
+                        .iter()
+                        .map(|arg| Argument {
+                            text: arg.to_token_stream().to_string(),
+                            range: span_range(arg.span()),
+                        })
+                        .collect(),
+                    sql_arg_no: config.sqlArgNo,
+                    enclosing_fn: self.fn_names.last().cloned(),
                 };
-
-                #[cfg(debug_assertions)]
-                println!("sql_node: {:#?}", sql_node);
-                self.sql_node_list
-                    .push(serde_json::to_string(&sql_node).unwrap());
+                match sql_arg {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(lit),
+                        ..
+                    }) => self.push_sql_node(
+                        &lit.token().to_string(),
+                        lit.span(),
+                        path_segment,
+                        dialect,
+                        call,
+                    ),
+                    _ => self.dynamic_sql_list.push(DynamicSql {
+                        call,
+                        dialect,
+                        expr: sql_arg.clone(),
+                    }),
+                }
                 break;
             }
         }
         visit::visit_expr_call(self, expr_call);
     }
+
+    fn visit_item_fn(&mut self, item_fn: &'ast syn::ItemFn) {
+        let inputs = fn_arg_types(&item_fn.sig);
+        self.fn_names.push(item_fn.sig.ident.to_string());
+        self.with_dialect_scope(inputs.into_iter(), |v| visit::visit_item_fn(v, item_fn));
+        self.fn_names.pop();
+    }
+
+    fn visit_item_impl(&mut self, item_impl: &'ast syn::ItemImpl) {
+        let self_ty = match &*item_impl.self_ty {
+            syn::Type::Path(type_path) => {
+                type_path.path.segments.last().map(|s| s.ident.to_string())
+            }
+            _ => None,
+        };
+        self.impl_types.push(self_ty.unwrap_or_default());
+        visit::visit_item_impl(self, item_impl);
+        self.impl_types.pop();
+    }
+
+    fn visit_item_trait(&mut self, item_trait: &'ast syn::ItemTrait) {
+        self.impl_types.push(item_trait.ident.to_string());
+        visit::visit_item_trait(self, item_trait);
+        self.impl_types.pop();
+    }
+
+    fn visit_impl_item_fn(&mut self, impl_item_fn: &'ast syn::ImplItemFn) {
+        let inputs = fn_arg_types(&impl_item_fn.sig);
+        self.fn_names
+            .push(self.method_name(&impl_item_fn.sig.ident));
+        self.with_dialect_scope(inputs.into_iter(), |v| {
+            visit::visit_impl_item_fn(v, impl_item_fn)
+        });
+        self.fn_names.pop();
+    }
+
+    fn visit_trait_item_fn(&mut self, trait_item_fn: &'ast syn::TraitItemFn) {
+        let inputs = fn_arg_types(&trait_item_fn.sig);
+        self.fn_names
+            .push(self.method_name(&trait_item_fn.sig.ident));
+        self.with_dialect_scope(inputs.into_iter(), |v| {
+            visit::visit_trait_item_fn(v, trait_item_fn)
+        });
+        self.fn_names.pop();
+    }
+
+    fn visit_expr_closure(&mut self, expr_closure: &'ast syn::ExprClosure) {
+        // ex) |conn: &mut SqliteConnection| { ... }
+        let inputs = expr_closure.inputs.iter().filter_map(|pat| match pat {
+            syn::Pat::Type(pat_type) => Some(&*pat_type.ty),
+            _ => None,
+        });
+        self.with_dialect_scope(inputs, |v| visit::visit_expr_closure(v, expr_closure));
+    }
+
+    fn visit_block(&mut self, block: &'ast syn::Block) {
+        // scope of the local annotations in the block
+        self.dialect_scopes.push(None);
+        visit::visit_block(self, block);
+        self.dialect_scopes.pop();
+    }
+
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        // ex) let conn: rusqlite::Connection = ...;
+        if let syn::Pat::Type(pat_type) = &local.pat {
+            if let Some(dialect) = dialect::infer_from_type(&pat_type.ty) {
+                if let Some(scope) = self.dialect_scopes.last_mut() {
+                    *scope = Some(dialect);
+                }
+            }
+        }
+        visit::visit_local(self, local);
+    }
+}
+
+/// Split macro tokens into comma separated arguments.
+fn macro_args(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut args = vec![vec![]];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == ',' => args.push(vec![]),
+            _ => args.last_mut().unwrap().push(token),
+        }
+    }
+    // trailing comma
+    args.retain(|arg| !arg.is_empty());
+    args
 }
 
+fn path_idents(path: &syn::Path) -> Vec<String> {
+    path.segments.iter().map(|s| s.ident.to_string()).collect()
+}
+
+fn fn_arg_types(sig: &syn::Signature) -> Vec<&syn::Type> {
+    sig.inputs
+        .iter()
+        .filter_map(|arg| match arg {
+            syn::FnArg::Typed(pat_type) => Some(&*pat_type.ty),
+            syn::FnArg::Receiver(_) => None,
+        })
+        .collect()
+}
+
+/// Parse the JSON configs passed from JS, falling back to the default sqlx configs.
+/// Invalid entries are skipped, `validate_sql_configs` reports them.
+fn parse_configs(configs: Option<Vec<String>>) -> Vec<Config> {
+    match configs {
+        Some(c) => validation::validate_configs(&c).0,
+        None => default_configs(),
+    }
+}
+
+fn visit_source(
+    source_txt: &str,
+    configs: &[Config],
+    default_dialect: Dialect,
+) -> Result<QueryVisitor, syn::Error> {
+    let ast: File = syn::parse_file(source_txt)?;
+    let mut query_visitor = QueryVisitor {
+        sql_node_list: Vec::<SqlNode>::new(),
+        dynamic_sql_list: Vec::<DynamicSql>::new(),
+        configs: configs.to_vec(),
+        default_dialect,
+        dialect_scopes: Vec::new(),
+        fn_names: Vec::new(),
+        impl_types: Vec::new(),
+    };
+    query_visitor.visit_file(&ast);
+    Ok(query_visitor)
+}
+
+/// Extract SQL nodes from Rust source code.
+/// `default_dialect` is used for queries whose dialect is neither inferred nor configured.
+pub fn extract_sql_nodes(
+    source_txt: &str,
+    configs: &[Config],
+    default_dialect: Dialect,
+) -> Result<Vec<SqlNode>, syn::Error> {
+    Ok(visit_source(source_txt, configs, default_dialect)?.sql_node_list)
+}
+
+/// Configured calls whose SQL argument is not a string literal.
+pub fn extract_dynamic_sql(
+    source_txt: &str,
+    configs: &[Config],
+    default_dialect: Dialect,
+) -> Result<Vec<DynamicSql>, syn::Error> {
+    Ok(visit_source(source_txt, configs, default_dialect)?.dynamic_sql_list)
+}
+
+/// Parse the dialect passed from JS, falling back to the generic dialect.
+fn parse_dialect(dialect: Option<String>) -> Option<Dialect> {
+    match dialect.as_deref().map(str::parse::<Dialect>) {
+        Some(Ok(d)) => Some(d),
+        Some(Err(err)) => {
+            eprintln!("Failed to parse dialect: {}", err);
+            None
+        }
+        None => Some(Dialect::default()),
+    }
+}
+
+/// Extract SQL nodes and report failures the way the wasm API does.
+fn extract_sql_nodes_or_empty(
+    source_txt: &str,
+    configs: &[Config],
+    default_dialect: Dialect,
+) -> Vec<SqlNode> {
+    match extract_sql_nodes(source_txt, configs, default_dialect) {
+        Ok(sql_nodes) => sql_nodes,
+        Err(err) => {
+            eprintln!("Failed to parse source code: {:?}", err);
+            Vec::<SqlNode>::new()
+        }
+    }
+}
+
+/// `dialect` is the fallback for queries whose dialect cannot be inferred.
 #[wasm_bindgen]
-pub fn extract_sql_list(source_txt: &str, configs: Option<Vec<String>>) -> SerializedSqlNodeList {
+pub fn extract_sql_list(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> SerializedSqlNodeList {
     // default is sqlx
-    let default_configs: Vec<Config> = vec![
-        Config {
-            functionName: "query".to_string(),
-            sqlArgNo: 0,
-            isMacro: true,
-        },
-        Config {
-            functionName: "query_as".to_string(),
-            sqlArgNo: 1,
-            isMacro: true,
-        },
-    ];
-
-    let configs: Vec<Config> = match configs {
-        Some(c) => {
-            match c
-                .iter()
-                .map(|c| serde_json::from_str(c))
-                .collect::<Result<Vec<_>, _>>()
-            {
-                Ok(c) => c,
-                Err(err) => {
-                    eprintln!("Failed to parse config: {:?}", err);
-                    return Vec::<String>::new();
-                }
-            }
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        // serialize sql_node to json
+        .map(|sql_node| serde_json::to_string(sql_node).unwrap())
+        .collect()
+}
+
+/// `dialect` is the fallback for queries whose dialect cannot be inferred.
+#[wasm_bindgen]
+pub fn check_sql_list(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> SerializedDiagnosticList {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .flat_map(syntax::check_syntax)
+        .map(|diagnostic| serde_json::to_string(&diagnostic).unwrap())
+        .collect()
+}
+
+/// Build a schema catalog by replaying migrations, so that no database is needed.
+/// `migrations` are JSON `{ "path": ..., "sql": ... }` in the order they are applied.
+#[wasm_bindgen]
+pub fn build_schema_catalog(migrations: Vec<String>, dialect: Option<String>) -> String {
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return serde_json::to_string(&Catalog::default()).unwrap(),
+    };
+    let migrations = match migrations
+        .iter()
+        .map(|m| serde_json::from_str(m))
+        .collect::<Result<Vec<Migration>, _>>()
+    {
+        Ok(m) => m,
+        Err(err) => {
+            eprintln!("Failed to parse migration: {:?}", err);
+            return serde_json::to_string(&Catalog::default()).unwrap();
+        }
+    };
+
+    let (catalog, errors) = Catalog::from_migrations(&migrations, dialect);
+    for error in errors {
+        eprintln!(
+            "Failed to replay migration {}: {}",
+            error.path, error.message
+        );
+    }
+    serde_json::to_string(&catalog).unwrap()
+}
+
+/// Build a schema catalog from the `table!` macros of a diesel `schema.rs`.
+#[wasm_bindgen]
+pub fn build_diesel_schema_catalog(schema_txt: &str) -> String {
+    let catalog = match Catalog::from_diesel_schema(schema_txt) {
+        Ok(catalog) => catalog,
+        Err(err) => {
+            eprintln!("Failed to parse diesel schema: {:?}", err);
+            Catalog::default()
+        }
+    };
+    serde_json::to_string(&catalog).unwrap()
+}
+
+/// Check the tables used by each query against a catalog built by
+/// `build_schema_catalog` or `build_diesel_schema_catalog`.
+#[wasm_bindgen]
+pub fn check_sql_schema(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    catalog: &str,
+) -> SerializedDiagnosticList {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+    let catalog: Catalog = match serde_json::from_str(catalog) {
+        Ok(catalog) => catalog,
+        Err(err) => {
+            eprintln!("Failed to parse catalog: {:?}", err);
+            return Vec::<String>::new();
+        }
+    };
+
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .flat_map(|sql_node| schema::check::check_schema(sql_node, &catalog))
+        .map(|diagnostic| serde_json::to_string(&diagnostic).unwrap())
+        .collect()
+}
+
+/// Compare `query_as!` target structs with the columns of their queries.
+/// `struct_sources` are other files of the crate where the structs may be declared,
+/// `catalog` (see `build_schema_catalog`) is used to expand `SELECT *`.
+#[wasm_bindgen]
+pub fn check_query_as_list(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    struct_sources: Option<Vec<String>>,
+    catalog: Option<String>,
+) -> SerializedDiagnosticList {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+    let catalog: Option<Catalog> = match catalog.as_deref().map(serde_json::from_str) {
+        Some(Ok(catalog)) => Some(catalog),
+        Some(Err(err)) => {
+            eprintln!("Failed to parse catalog: {:?}", err);
+            return Vec::<String>::new();
+        }
+        None => None,
+    };
+    let structs: Vec<query_as::RecordStruct> = std::iter::once(source_txt)
+        .chain(struct_sources.iter().flatten().map(String::as_str))
+        .filter_map(|source| query_as::collect_structs(source).ok())
+        .flatten()
+        .collect();
+
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .flat_map(|sql_node| query_as::check_query_as(sql_node, &structs, catalog.as_ref()))
+        .map(|diagnostic| serde_json::to_string(&diagnostic).unwrap())
+        .collect()
+}
+
+// JSON `{ "path": ..., "content": ... }`
+fn parse_source_files(sources: Vec<String>) -> Option<Vec<workspace::SourceFile>> {
+    match sources
+        .iter()
+        .map(|s| serde_json::from_str(s))
+        .collect::<Result<Vec<workspace::SourceFile>, _>>()
+    {
+        Ok(sources) => Some(sources),
+        Err(err) => {
+            eprintln!("Failed to parse source file: {:?}", err);
+            None
+        }
+    }
+}
+
+// JSON `{ "path": ..., "content": ... }` of the files in `.sqlx`
+fn parse_cache_files(cache_files: Vec<String>) -> Option<offline::OfflineCache> {
+    parse_source_files(cache_files).map(|files| offline::OfflineCache::from_files(&files))
+}
+
+/// Extract SQL nodes with the sqlx offline metadata (`offline`) of each `query!` family macro.
+#[wasm_bindgen]
+pub fn link_offline_cache(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    cache_files: Vec<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let (dialect, cache) = match (parse_dialect(dialect), parse_cache_files(cache_files)) {
+        (Some(d), Some(cache)) => (d, cache),
+        _ => return Vec::<String>::new(),
+    };
+
+    let mut sql_nodes = extract_sql_nodes_or_empty(source_txt, &configs, dialect);
+    offline::link(&mut sql_nodes, &cache);
+    sql_nodes
+        .iter()
+        .map(|sql_node| serde_json::to_string(sql_node).unwrap())
+        .collect()
+}
+
+/// Report `query!` family macros whose query has no metadata in the sqlx offline cache.
+#[wasm_bindgen]
+pub fn check_offline_cache(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    cache_files: Vec<String>,
+) -> SerializedDiagnosticList {
+    let configs = parse_configs(configs);
+    let (dialect, cache) = match (parse_dialect(dialect), parse_cache_files(cache_files)) {
+        (Some(d), Some(cache)) => (d, cache),
+        _ => return Vec::<String>::new(),
+    };
+
+    let mut sql_nodes = extract_sql_nodes_or_empty(source_txt, &configs, dialect);
+    offline::link(&mut sql_nodes, &cache)
+        .iter()
+        .map(|diagnostic| serde_json::to_string(diagnostic).unwrap())
+        .collect()
+}
+
+/// Report sqlx offline cache files that no query of `source_txts` (all the files of a workspace) uses.
+#[wasm_bindgen]
+pub fn find_orphaned_cache_entries(
+    source_txts: Vec<String>,
+    configs: Option<Vec<String>>,
+    cache_files: Vec<String>,
+) -> SerializedDiagnosticList {
+    let configs = parse_configs(configs);
+    let cache = match parse_cache_files(cache_files) {
+        Some(cache) => cache,
+        None => return Vec::<String>::new(),
+    };
+
+    let sql_nodes: Vec<SqlNode> = source_txts
+        .iter()
+        .flat_map(|source_txt| extract_sql_nodes_or_empty(source_txt, &configs, Dialect::default()))
+        .collect();
+    offline::orphaned_entries(&sql_nodes, &cache)
+        .iter()
+        .map(|diagnostic| serde_json::to_string(diagnostic).unwrap())
+        .collect()
+}
+
+/// Hover for a position (0-indexed) in a sqlx macro query or on its macro path, `null` if there is none.
+#[wasm_bindgen]
+pub fn hover_sql(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    cache_files: Vec<String>,
+    line: usize,
+    character: usize,
+) -> String {
+    let configs = parse_configs(configs);
+    let (dialect, cache) = match (parse_dialect(dialect), parse_cache_files(cache_files)) {
+        (Some(d), Some(cache)) => (d, cache),
+        _ => return "null".to_string(),
+    };
+
+    let mut sql_nodes = extract_sql_nodes_or_empty(source_txt, &configs, dialect);
+    offline::link(&mut sql_nodes, &cache);
+    serde_json::to_string(&hover::hover(&sql_nodes, &Position { line, character })).unwrap()
+}
+
+/// Extract SQL nodes with the tables, columns, aliases, CTEs and functions (`references`) of each query.
+#[wasm_bindgen]
+pub fn extract_sql_references(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    let mut sql_nodes = extract_sql_nodes_or_empty(source_txt, &configs, dialect);
+    references::link(&mut sql_nodes);
+    sql_nodes
+        .iter()
+        .map(|sql_node| serde_json::to_string(sql_node).unwrap())
+        .collect()
+}
+
+/// Rename a table, or a column of a table, in the queries of `sources` (JSON `{ "path": ..., "content": ... }`).
+/// `target` is JSON `{ "table": ..., "column": ... }`. Returns the text edits of each file.
+#[wasm_bindgen]
+pub fn rename_sql_identifier(
+    sources: Vec<String>,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    target: &str,
+    new_name: &str,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+    let target: rename::RenameTarget = match serde_json::from_str(target) {
+        Ok(target) => target,
+        Err(err) => {
+            eprintln!("Failed to parse rename target: {:?}", err);
+            return Vec::<String>::new();
         }
-        None => default_configs,
+    };
+    let sources = match parse_source_files(sources) {
+        Some(sources) => sources,
+        None => return Vec::<String>::new(),
     };
 
-    let ast: File = match syn::parse_file(source_txt) {
-        Ok(ast) => ast,
+    rename::rename_in_sources(&sources, &configs, dialect, &target, new_name)
+        .iter()
+        .map(|file_edit| serde_json::to_string(file_edit).unwrap())
+        .collect()
+}
+
+/// Definition in `migrations` (JSON `{ "path": ..., "sql": ... }` in the order they are applied)
+/// of the table or column at a position (0-indexed) of a query, `null` if there is none.
+#[wasm_bindgen]
+pub fn find_sql_definition(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    migrations: Vec<String>,
+    line: usize,
+    character: usize,
+) -> String {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return "null".to_string(),
+    };
+    let migrations = match migrations
+        .iter()
+        .map(|m| serde_json::from_str(m))
+        .collect::<Result<Vec<Migration>, _>>()
+    {
+        Ok(m) => m,
+        Err(err) => {
+            eprintln!("Failed to parse migration: {:?}", err);
+            return "null".to_string();
+        }
+    };
+
+    let position = Position { line, character };
+    let definition = extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .filter(|sql_node| sql_node.literal.range.contains(&position))
+        .find_map(|sql_node| {
+            definition::find_definition(sql_node, &position, &migrations, sql_node.dialect)
+        });
+    serde_json::to_string(&definition).unwrap()
+}
+
+/// Report configured calls whose SQL is built from runtime values with `format!`, `concat!` or `+`.
+#[wasm_bindgen]
+pub fn check_sql_injection(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> SerializedDiagnosticList {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    match extract_dynamic_sql(source_txt, &configs, dialect) {
+        Ok(dynamic_sql_list) => dynamic_sql_list
+            .iter()
+            .filter_map(|dynamic_sql| injection::check_injection(source_txt, dynamic_sql))
+            .map(|injection| serde_json::to_string(&injection).unwrap())
+            .collect(),
         Err(err) => {
             eprintln!("Failed to parse source code: {:?}", err);
+            Vec::<String>::new()
+        }
+    }
+}
+
+/// Every configured call in source order: literal ones with `content`, others with a `dynamic` classification.
+#[wasm_bindgen]
+pub fn report_sql_call_sites(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    match audit::report_call_sites(source_txt, &configs, dialect) {
+        Ok(reports) => reports
+            .iter()
+            .map(|report| serde_json::to_string(report).unwrap())
+            .collect(),
+        Err(err) => {
+            eprintln!("Failed to parse source code: {:?}", err);
+            Vec::<String>::new()
+        }
+    }
+}
+
+/// Extract SQL nodes with the fingerprint (hash of the normalized SQL) of each query.
+#[wasm_bindgen]
+pub fn extract_sql_fingerprints(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    let mut sql_nodes = extract_sql_nodes_or_empty(source_txt, &configs, dialect);
+    fingerprint::link(&mut sql_nodes);
+    sql_nodes
+        .iter()
+        .map(|sql_node| serde_json::to_string(sql_node).unwrap())
+        .collect()
+}
+
+/// Group the queries of `sources` (JSON `{ "path": ..., "content": ... }`) that normalize to the same SQL.
+#[wasm_bindgen]
+pub fn find_duplicate_queries(
+    sources: Vec<String>,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let (dialect, sources) = match (parse_dialect(dialect), parse_source_files(sources)) {
+        (Some(d), Some(s)) => (d, s),
+        _ => return Vec::<String>::new(),
+    };
+
+    fingerprint::find_duplicates(&sources, &configs, dialect)
+        .iter()
+        .map(|group| serde_json::to_string(group).unwrap())
+        .collect()
+}
+
+/// Join the rows of a `pg_stat_statements` CSV export to the call sites of `sources` that issue each query.
+#[wasm_bindgen]
+pub fn join_pg_stats(
+    stats_csv: &str,
+    sources: Vec<String>,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let (dialect, sources) = match (parse_dialect(dialect), parse_source_files(sources)) {
+        (Some(d), Some(s)) => (d, s),
+        _ => return Vec::<String>::new(),
+    };
+    let rows = match pg_stats::read_stats_csv(stats_csv) {
+        Ok(rows) => rows,
+        Err(err) => {
+            eprintln!("Failed to read stats CSV: {}", err);
             return Vec::<String>::new();
         }
     };
-    let mut query_visitor = QueryVisitor {
-        sql_node_list: Vec::<String>::new(),
-        configs,
+
+    pg_stats::join_stats(rows, &sources, &configs, dialect)
+        .iter()
+        .map(|stats_match| serde_json::to_string(stats_match).unwrap())
+        .collect()
+}
+
+/// Edits converting the placeholders of the query at a position (0-indexed) to `style`
+/// ("dollar" or "question"), with the bind arguments reordered to match.
+#[wasm_bindgen]
+pub fn convert_sql_placeholders(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    line: usize,
+    character: usize,
+    style: &str,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+    let style: placeholder::PlaceholderStyle = match style.parse() {
+        Ok(style) => style,
+        Err(err) => {
+            eprintln!("Failed to parse placeholder style: {}", err);
+            return Vec::<String>::new();
+        }
     };
-    query_visitor.visit_file(&ast);
 
-    query_visitor.sql_node_list
+    let position = Position { line, character };
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .find(|sql_node| sql_node.literal.range.contains(&position))
+        .and_then(|sql_node| placeholder::convert_placeholders(source_txt, sql_node, style))
+        .unwrap_or_default()
+        .iter()
+        .map(|edit| serde_json::to_string(edit).unwrap())
+        .collect()
+}
+
+/// Edits formatting every query of a source, re-encoded for the kind of each literal.
+/// `options` is JSON `{ "indent": "    ", "promoteRawStrings": true, "demoteMaxLength": null,
+/// "layout": { ... } }`, defaults are used for what is not given.
+#[wasm_bindgen]
+pub fn format_sql_list(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    options: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+    let options: format::FormatOptions = match options.map(|o| serde_json::from_str(&o)) {
+        None => Default::default(),
+        Some(Ok(options)) => options,
+        Some(Err(err)) => {
+            eprintln!("Failed to parse format options: {:?}", err);
+            return Vec::<String>::new();
+        }
+    };
+
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .filter_map(|sql_node| format::format_edits(source_txt, sql_node, &options))
+        .flatten()
+        .map(|edit| serde_json::to_string(&edit).unwrap())
+        .collect()
+}
+
+/// Move the SQL of the query at a position (0-indexed) to a `const` or to a `.sql` file
+/// (`target` is "const" or "file"), `null` if the call cannot take it from there.
+/// `query_files` are the names of the files already in `queries/`.
+#[wasm_bindgen]
+pub fn extract_sql_query(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    line: usize,
+    character: usize,
+    target: &str,
+    query_files: Option<Vec<String>>,
+) -> String {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return "null".to_string(),
+    };
+    let target: extract_query::ExtractTarget = match target.parse() {
+        Ok(target) => target,
+        Err(err) => {
+            eprintln!("Failed to parse extract target: {}", err);
+            return "null".to_string();
+        }
+    };
+
+    let position = Position { line, character };
+    let extraction = extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .find(|sql_node| sql_node.literal.range.contains(&position))
+        .and_then(|sql_node| {
+            extract_query::extract_query(
+                source_txt,
+                sql_node,
+                target,
+                &query_files.unwrap_or_default(),
+            )
+        });
+    serde_json::to_string(&extraction).unwrap()
+}
+
+/// Edits converting the call of the query at a position (0-indexed) between a runtime
+/// function with `.bind()` and the sqlx macro, in either direction.
+#[wasm_bindgen]
+pub fn convert_sql_query_call(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    line: usize,
+    character: usize,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+
+    let position = Position { line, character };
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .find(|sql_node| sql_node.call.range.contains(&position))
+        .and_then(|sql_node| convert_call::convert_query_call(source_txt, sql_node))
+        .unwrap_or_default()
+        .iter()
+        .map(|edit| serde_json::to_string(edit).unwrap())
+        .collect()
+}
+
+/// Style diagnostics of every query of a source, with the edits fixing them when there is
+/// an obvious one. `lint_config` is JSON `{ "rules": { "keyword-case": "off", ... } }`, rules
+/// that are not listed report with their default severity.
+#[wasm_bindgen]
+pub fn lint_sql_list(
+    source_txt: &str,
+    configs: Option<Vec<String>>,
+    dialect: Option<String>,
+    lint_config: Option<String>,
+) -> Vec<String> {
+    let configs = parse_configs(configs);
+    let dialect = match parse_dialect(dialect) {
+        Some(d) => d,
+        None => return Vec::<String>::new(),
+    };
+    let lint_config: lint::LintConfig = match lint_config.map(|c| serde_json::from_str(&c)) {
+        None => Default::default(),
+        Some(Ok(lint_config)) => lint_config,
+        Some(Err(err)) => {
+            eprintln!("Failed to parse lint config: {:?}", err);
+            return Vec::<String>::new();
+        }
+    };
+
+    extract_sql_nodes_or_empty(source_txt, &configs, dialect)
+        .iter()
+        .flat_map(|sql_node| lint::lint(sql_node, &lint_config))
+        .map(|diagnostic| serde_json::to_string(&diagnostic).unwrap())
+        .collect()
+}
+
+/// Problems of JSON configs, naming the entry, the field and the expected type.
+/// The other functions skip invalid entries silently and use the valid ones, so this is
+/// the way to report them.
+#[wasm_bindgen]
+pub fn validate_sql_configs(configs: Vec<String>) -> Vec<String> {
+    let (_, errors) = validation::validate_configs(&configs);
+    errors
+        .iter()
+        .map(|err| serde_json::to_string(err).unwrap())
+        .collect()
+}
+
+/// JSON schema of the project config, see `validation::config_schema`.
+#[wasm_bindgen]
+pub fn sql_config_schema() -> String {
+    validation::config_schema()
 }
 
 #[cfg(test)]
@@ -352,6 +1283,7 @@ async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
 }
         "#,
             None,
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         let expected = serde_json::to_string(&SqlNode {
@@ -367,6 +1299,8 @@ async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
             },
             content: "INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id".to_string(),
             method_line: 2,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         })
         .unwrap();
 
@@ -402,6 +1336,7 @@ ORDER BY id
 }
             "##,
             None,
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         assert_eq!(result.len(), 1);
@@ -420,6 +1355,8 @@ ORDER BY id
                 "\nSELECT id, description, done\nFROM todos\nWHERE id = ?\nORDER BY id\n        "
                     .to_string(),
             method_line: 2,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         })
         .unwrap();
         assert_eq!(result[0], expected);
@@ -463,15 +1400,18 @@ ORDER BY id
                     functionName: "query".to_string(),
                     sqlArgNo: 0,
                     isMacro: true,
+                    dialect: None,
                 })
                 .unwrap(),
                 serde_json::to_string(&Config {
                     functionName: "query_as".to_string(),
                     sqlArgNo: 1,
                     isMacro: true,
+                    dialect: None,
                 })
                 .unwrap(),
             ]),
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         assert_eq!(result.len(), 2);
@@ -488,6 +1428,8 @@ ORDER BY id
             },
             content: "INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id".to_string(),
             method_line: 2,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         });
         let expected2 = serde_json::to_string(&SqlNode {
             code_range: Range {
@@ -504,6 +1446,8 @@ ORDER BY id
                 "\nSELECT id, description, done\nFROM todos\nWHERE id = ?\nORDER BY id\n        "
                     .to_string(),
             method_line: 8,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         });
         assert_eq!(result[0], expected1.unwrap());
         assert_eq!(result[1], expected2.unwrap());
@@ -527,6 +1471,7 @@ async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
 }
             "#,
             None,
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         let expected1 = serde_json::to_string(&SqlNode {
@@ -542,6 +1487,8 @@ async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
             },
             content: "SELECT id \\\"id\\\", description, done FROM todos ORDER BY id".to_string(),
             method_line: 2,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         })
         .unwrap();
 
@@ -558,6 +1505,8 @@ async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
             },
             content: "INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id".to_string(),
             method_line: 7,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         })
         .unwrap();
 
@@ -587,6 +1536,7 @@ WHERE id = $1
 }
             "##,
             None,
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         assert_eq!(result.len(), 1);
@@ -604,6 +1554,8 @@ WHERE id = $1
             },
             content: "\nUPDATE todos\nSET done = TRUE\nWHERE id = $1\n        ".to_string(),
             method_line: 2,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         })
         .unwrap();
         assert_eq!(result[0], expected,);
@@ -639,6 +1591,7 @@ RETURNING id
 }
             "##,
             None,
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         assert_eq!(result.len(), 2);
@@ -657,6 +1610,8 @@ RETURNING id
                 "\nINSERT INTO \"todos\" ( description )\nVALUES ( $1 )\nRETURNING id\n        "
                     .to_string(),
             method_line: 2,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         })
         .unwrap();
         assert_eq!(result[0], expected1);
@@ -671,6 +1626,8 @@ RETURNING id
             },
             content: "\n            UPDATE todos\n            SET done = TRUE\n            WHERE id = $1\n            ".to_string(),
             method_line: 13,
+            dialect: Dialect::PostgreSql,
+            ..Default::default()
         }).unwrap();
         assert_eq!(result[1], expected2);
     }
@@ -690,6 +1647,7 @@ async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
     Ok(rec.id)
         "##,
             None,
+            None,
         );
 
         println!("{} result: {:?}", function!(), result);
@@ -720,10 +1678,12 @@ ORDER BY id
                     functionName: "sql_query".to_string(),
                     sqlArgNo: 0,
                     isMacro: false,
+                    dialect: None,
                 })
                 .unwrap()]
                 .to_vec(),
             ),
+            None,
         );
         println!("{} result: {:?}", function!(), result);
         let expected = serde_json::to_string(&SqlNode {
@@ -740,6 +1700,7 @@ ORDER BY id
             content: "\nSELECT id, description, done\nFROM todos\nORDER BY id\n        "
                 .to_string(),
             method_line: 4,
+            ..Default::default()
         })
         .unwrap();
 
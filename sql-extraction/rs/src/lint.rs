@@ -0,0 +1,388 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::edit::TextEdit;
+use crate::literal::offset_of_location;
+use crate::syntax::parse_each_statement;
+use crate::{Range, SqlNode};
+use schemars::{json_schema, JsonSchema, Schema, SchemaGenerator};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlparser::ast::Statement;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::collections::BTreeMap;
+
+/// Severity of a rule, or `off` to disable it.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    Off,
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// Configured rules, ex) `{ "rules": { "keyword-case": "off", "no-select-star": "error" } }`.
+/// Rules that are not listed report with their default severity.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct LintConfig {
+    /// severity by rule id, an unknown id is an error rather than ignored
+    #[serde(deserialize_with = "known_rules")]
+    #[schemars(schema_with = "rules_schema")]
+    pub rules: BTreeMap<String, RuleLevel>,
+}
+
+fn known_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, RuleLevel>, D::Error> {
+    let rules = BTreeMap::<String, RuleLevel>::deserialize(deserializer)?;
+    match rules
+        .keys()
+        .find(|id| !RULES.iter().any(|rule| rule.id == id.as_str()))
+    {
+        Some(id) => Err(D::Error::custom(format!(
+            "unknown rule `{}`, expected one of {}",
+            id,
+            RULES.map(|rule| rule.id).join(", ")
+        ))),
+        None => Ok(rules),
+    }
+}
+
+fn rules_schema(generator: &mut SchemaGenerator) -> Schema {
+    let level = generator.subschema_for::<RuleLevel>();
+    let properties: serde_json::Map<String, serde_json::Value> = RULES
+        .iter()
+        .map(|rule| (rule.id.to_string(), level.clone().into()))
+        .collect();
+    json_schema!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+impl LintConfig {
+    /// Severity `rule` reports with, `None` if it is turned off.
+    pub fn severity(&self, rule: &LintRule) -> Option<Severity> {
+        match self.rules.get(rule.id) {
+            None => Some(rule.default_severity),
+            Some(RuleLevel::Off) => None,
+            Some(RuleLevel::Error) => Some(Severity::Error),
+            Some(RuleLevel::Warning) => Some(Severity::Warning),
+            Some(RuleLevel::Information) => Some(Severity::Information),
+            Some(RuleLevel::Hint) => Some(Severity::Hint),
+        }
+    }
+}
+
+/// A style problem of the SQL of a node, with the edits fixing it when there is an obvious one.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct LintDiagnostic {
+    #[serde(flatten)]
+    pub diagnostic: Diagnostic,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<Vec<TextEdit>>,
+}
+
+/// A check of the SQL of a node. Its id is the code of the diagnostics it reports.
+pub struct LintRule {
+    pub id: &'static str,
+    pub default_severity: Severity,
+    check: fn(&SqlNode) -> Vec<Finding>,
+}
+
+struct Finding {
+    range: Range,
+    message: String,
+    fix: Option<Vec<TextEdit>>,
+}
+
+pub const RULES: [LintRule; 4] = [
+    LintRule {
+        id: "keyword-case",
+        default_severity: Severity::Information,
+        check: keyword_case,
+    },
+    LintRule {
+        id: "no-select-star",
+        default_severity: Severity::Warning,
+        check: no_select_star,
+    },
+    LintRule {
+        id: "no-trailing-semicolon",
+        default_severity: Severity::Warning,
+        check: no_trailing_semicolon,
+    },
+    LintRule {
+        id: "insert-column-list",
+        default_severity: Severity::Warning,
+        check: insert_column_list,
+    },
+];
+
+/// Run the rules that are not turned off by `config` on the SQL of a node.
+pub fn lint(sql_node: &SqlNode, config: &LintConfig) -> Vec<LintDiagnostic> {
+    RULES
+        .iter()
+        .filter_map(|rule| Some((rule, config.severity(rule)?)))
+        .flat_map(|(rule, severity)| {
+            (rule.check)(sql_node)
+                .into_iter()
+                .map(move |finding| LintDiagnostic {
+                    diagnostic: Diagnostic {
+                        range: finding.range,
+                        severity,
+                        code: rule.id.to_string(),
+                        message: finding.message,
+                    },
+                    fix: finding.fix,
+                })
+        })
+        .collect()
+}
+
+// keywords that are never taken for identifiers in practice, unlike `id`, `name` or `date`
+const KEYWORDS: &str = "ALL AND AS ASC BETWEEN BY CASE CONFLICT CROSS DEFAULT DELETE DESC \
+    DISTINCT DO ELSE END EXCEPT EXISTS FALSE FETCH FOR FROM FULL GROUP HAVING ILIKE IN INNER \
+    INSERT INTERSECT INTO IS JOIN LATERAL LEFT LIKE LIMIT NOT NOTHING NULL OFFSET ON OR ORDER \
+    OUTER RETURNING RIGHT SELECT SET THEN TRUE UNION UPDATE USING VALUES WHEN WHERE WITH";
+
+/// Tokens of the SQL of a node with their char offsets, whitespace and comments excluded.
+/// Empty if it cannot be tokenized, that is reported as a syntax error.
+fn tokens(sql_node: &SqlNode) -> Vec<(Token, usize, usize)> {
+    let sql = &sql_node.literal.value;
+    let parser_dialect = sql_node.dialect.parser_dialect();
+    let tokens = match Tokenizer::new(&*parser_dialect, sql).tokenize_with_location() {
+        Ok(tokens) => tokens,
+        Err(_) => return vec![],
+    };
+    tokens
+        .into_iter()
+        .filter(|t| !matches!(t.token, Token::Whitespace(_) | Token::EOF))
+        .map(|t| {
+            let start = offset_of_location(sql, t.span.start.line, t.span.start.column);
+            let end = offset_of_location(sql, t.span.end.line, t.span.end.column);
+            (t.token, start, end)
+        })
+        .collect()
+}
+
+fn keyword_case(sql_node: &SqlNode) -> Vec<Finding> {
+    let tokens = tokens(sql_node);
+    tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (token, start, end))| {
+            let word = match token {
+                Token::Word(word) if word.quote_style.is_none() => &word.value,
+                _ => return None,
+            };
+            let upper = word.to_uppercase();
+            // `t.end`, `"order".desc` are names
+            let qualified = [i.checked_sub(1), Some(i + 1)]
+                .iter()
+                .any(|j| matches!(j.and_then(|j| tokens.get(j)), Some((Token::Period, ..))));
+            if *word == upper || qualified || !KEYWORDS.split_whitespace().any(|k| k == upper) {
+                return None;
+            }
+            let range = sql_node.literal.range_of(*start, *end);
+            Some(Finding {
+                range,
+                message: format!("keyword `{}` should be uppercase", word),
+                fix: Some(vec![TextEdit {
+                    range,
+                    new_text: upper,
+                }]),
+            })
+        })
+        .collect()
+}
+
+fn no_select_star(sql_node: &SqlNode) -> Vec<Finding> {
+    let call = &sql_node.call;
+    if !call.is_macro || !["query_as", "query_as_unchecked"].contains(&call.function_name()) {
+        return vec![];
+    }
+    let tokens = tokens(sql_node);
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, (token, ..))| {
+            // a wildcard of the projection, not `count(*)` or `a * b`
+            *token == Token::Mul
+                && match i.checked_sub(1).map(|j| &tokens[j].0) {
+                    Some(Token::Word(word)) => {
+                        ["SELECT", "DISTINCT", "ALL"].contains(&word.value.to_uppercase().as_str())
+                    }
+                    Some(Token::Comma | Token::Period) => true,
+                    _ => false,
+                }
+        })
+        .map(|(_, (_, start, end))| Finding {
+            range: sql_node.literal.range_of(*start, *end),
+            message: format!(
+                "`SELECT *` in `{}!` depends on the columns of the table, list the fields of the record instead",
+                call.function_name()
+            ),
+            fix: None,
+        })
+        .collect()
+}
+
+fn no_trailing_semicolon(sql_node: &SqlNode) -> Vec<Finding> {
+    if !sql_node.call.is_macro {
+        return vec![];
+    }
+    let tokens = tokens(sql_node);
+    let semicolons: Vec<&(Token, usize, usize)> = tokens
+        .iter()
+        .filter(|(token, ..)| *token == Token::SemiColon)
+        .collect();
+    match (semicolons.as_slice(), tokens.last()) {
+        // a single statement ending with `;`
+        ([semicolon], Some(last)) if semicolon == &last => {
+            let range = sql_node.literal.range_of(last.1, last.2);
+            vec![Finding {
+                range,
+                message: format!(
+                    "`{}!` takes a single statement, drop the trailing `;`",
+                    sql_node.call.function_name()
+                ),
+                fix: Some(vec![TextEdit {
+                    range,
+                    new_text: String::new(),
+                }]),
+            }]
+        }
+        _ => vec![],
+    }
+}
+
+fn insert_column_list(sql_node: &SqlNode) -> Vec<Finding> {
+    let sql = &sql_node.literal.value;
+    parse_each_statement(sql, sql_node.dialect)
+        .into_iter()
+        .filter_map(|parsed| match parsed.statement {
+            Ok(Statement::Insert(insert)) if insert.columns.is_empty() && insert.source.is_some() => {
+                let first = insert.table_name.0.first()?.span.start;
+                let last = insert.table_name.0.last()?.span.end;
+                let start = offset_of_location(sql, first.line, first.column);
+                let end = offset_of_location(sql, last.line, last.column);
+                Some(Finding {
+                    range: sql_node.literal.range_of(start, end),
+                    message: format!(
+                        "`INSERT INTO {}` depends on the order of the columns of the table, list the columns",
+                        insert.table_name
+                    ),
+                    fix: None,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{default_configs, extract_sql_nodes, Position};
+    use pretty_assertions::assert_eq;
+
+    fn lint_source(source_txt: &str, config: &LintConfig) -> Vec<LintDiagnostic> {
+        extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql)
+            .unwrap()
+            .iter()
+            .flat_map(|sql_node| lint(sql_node, config))
+            .collect()
+    }
+
+    fn range(line: usize, start: usize, end: usize) -> Range {
+        Range {
+            start: Position {
+                line,
+                character: start,
+            },
+            end: Position {
+                line,
+                character: end,
+            },
+        }
+    }
+
+    #[test]
+    fn lowercase_keywords_with_fix() {
+        let result = lint_source(
+            r#"fn f() { sqlx::query!("select id, t.end from todos t where done IS NOT NULL") }"#,
+            &LintConfig::default(),
+        );
+        let fixes: Vec<(Range, String)> = result
+            .iter()
+            .flat_map(|d| d.fix.clone().unwrap())
+            .map(|edit| (edit.range, edit.new_text))
+            .collect();
+        // `t.end` is a column, `id` is not taken for a keyword
+        assert_eq!(
+            fixes,
+            vec![
+                (range(0, 23, 29), "SELECT".to_string()),
+                (range(0, 40, 44), "FROM".to_string()),
+                (range(0, 53, 58), "WHERE".to_string()),
+            ]
+        );
+        assert_eq!(result[0].diagnostic.code, "keyword-case");
+        assert_eq!(result[0].diagnostic.severity, Severity::Information);
+    }
+
+    #[test]
+    fn select_star_semicolon_and_insert_columns() {
+        let result = lint_source(
+            r#"
+async fn f(pool: &PgPool) {
+    sqlx::query_as!(Todo, "SELECT t.* FROM todos t;");
+    sqlx::query!("SELECT count(*) FROM todos");
+    sqlx::query!("INSERT INTO todos VALUES ($1, $2)", id, description);
+}
+"#,
+            &LintConfig::default(),
+        );
+        let codes: Vec<(&str, Range, bool)> = result
+            .iter()
+            .map(|d| {
+                (
+                    d.diagnostic.code.as_str(),
+                    d.diagnostic.range,
+                    d.fix.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("no-select-star", range(2, 36, 37), false),
+                ("no-trailing-semicolon", range(2, 50, 51), true),
+                ("insert-column-list", range(4, 30, 35), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_severities() {
+        let config: LintConfig = serde_json::from_str(
+            r#"{ "rules": { "keyword-case": "off", "no-trailing-semicolon": "error" } }"#,
+        )
+        .unwrap();
+        let result = lint_source(r#"fn f() { sqlx::query!("select 1;") }"#, &config);
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].diagnostic.code, "no-trailing-semicolon");
+        assert_eq!(result[0].diagnostic.severity, Severity::Error);
+
+        let err = serde_json::from_str::<LintConfig>(r#"{ "rules": { "keyword_case": "off" } }"#)
+            .unwrap_err();
+        assert!(err.to_string().starts_with(
+            "unknown rule `keyword_case`, expected one of keyword-case, no-select-star, \
+             no-trailing-semicolon, insert-column-list"
+        ));
+    }
+}
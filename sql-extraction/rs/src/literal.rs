@@ -0,0 +1,290 @@
+use crate::{Position, Range};
+
+/// Delimiter kind of a Rust string literal holding SQL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LiteralKind {
+    /// `"..."`, escapes are interpreted
+    #[default]
+    Str,
+    /// `r"..."`, `r#"..."#`, ... (`hashes` is the number of `#`)
+    RawStr { hashes: usize },
+}
+
+impl LiteralKind {
+    /// Source text of `text` inside a literal of this kind.
+    pub fn escape(&self, text: &str) -> String {
+        match self {
+            LiteralKind::Str => text.replace('\\', "\\\\").replace('"', "\\\""),
+            LiteralKind::RawStr { .. } => text.to_string(),
+        }
+    }
+}
+
+/// A Rust string literal decoded into its SQL value, with a map from every
+/// character of the value back to its position in the Rust source.
+#[derive(Debug, Clone, Default)]
+pub struct SqlLiteral {
+    pub kind: LiteralKind,
+    /// range of the whole literal token, delimiters included
+    pub range: Range,
+    /// unescaped string value
+    pub value: String,
+    /// source position of each char of `value`, plus one for the end of the body
+    offsets: Vec<Position>,
+}
+
+impl SqlLiteral {
+    /// Decode a literal token as printed by proc_macro2 (e.g. `"SELECT \"id\""`
+    /// or `r#"SELECT 1"#`). `start` is the position of the first char of the token.
+    /// Returns `None` for anything that is not a (raw) string literal.
+    pub fn parse(token: &str, start: Position) -> Option<SqlLiteral> {
+        let (kind, body_start, body) = if let Some(rest) = token.strip_prefix('r') {
+            let hashes = rest.chars().take_while(|c| *c == '#').count();
+            let closing = format!("\"{}", "#".repeat(hashes));
+            let body = rest[hashes..]
+                .strip_prefix('"')?
+                .strip_suffix(closing.as_str())?;
+            (LiteralKind::RawStr { hashes }, 1 + hashes + 1, body)
+        } else {
+            let body = token.strip_prefix('"')?.strip_suffix('"')?;
+            (LiteralKind::Str, 1, body)
+        };
+
+        // positions of each char of the token body
+        let mut positions = Vec::with_capacity(body.len() + 1);
+        let mut pos = advance(&start, &token[..body_start]);
+        for c in body.chars() {
+            positions.push(pos);
+            pos = advance_char(&pos, c);
+        }
+        positions.push(pos);
+
+        let (value, offsets) = match kind {
+            LiteralKind::RawStr { .. } => (body.to_string(), positions),
+            LiteralKind::Str => unescape(body, &positions)?,
+        };
+        let end = advance(&start, token);
+
+        Some(SqlLiteral {
+            kind,
+            range: Range { start, end },
+            value,
+            offsets,
+        })
+    }
+
+    /// Source position of the char at `offset` (char index into `value`).
+    pub fn position_at(&self, offset: usize) -> Position {
+        self.offsets[offset.min(self.offsets.len() - 1)]
+    }
+
+    /// Source range of the chars `start..end` of `value`.
+    pub fn range_of(&self, start: usize, end: usize) -> Range {
+        Range {
+            start: self.position_at(start),
+            end: self.position_at(end),
+        }
+    }
+
+    /// Source range of the literal body, delimiters excluded.
+    pub fn body_range(&self) -> Range {
+        self.range_of(0, self.offsets.len() - 1)
+    }
+
+    /// Char index into `value` of a source position, if it is inside the body.
+    pub fn offset_at(&self, position: &Position) -> Option<usize> {
+        if position < self.offsets.first()? || position > self.offsets.last()? {
+            return None;
+        }
+        // an escape sequence maps to the char it starts
+        self.offsets.iter().rposition(|p| p <= position)
+    }
+}
+
+/// Char index into `sql` of a sqlparser location (1-indexed line and column).
+pub fn offset_of_location(sql: &str, line: u64, column: u64) -> usize {
+    let mut offset = 0;
+    for (i, l) in sql.split('\n').enumerate() {
+        if i as u64 + 1 == line {
+            return offset + (column.saturating_sub(1) as usize).min(l.chars().count());
+        }
+        offset += l.chars().count() + 1;
+    }
+    sql.chars().count()
+}
+
+fn advance_char(pos: &Position, c: char) -> Position {
+    if c == '\n' {
+        Position {
+            line: pos.line + 1,
+            character: 0,
+        }
+    } else {
+        Position {
+            line: pos.line,
+            character: pos.character + 1,
+        }
+    }
+}
+
+pub(crate) fn advance(pos: &Position, text: &str) -> Position {
+    text.chars().fold(*pos, |p, c| advance_char(&p, c))
+}
+
+// https://doc.rust-lang.org/reference/tokens.html#string-literals
+fn unescape(body: &str, positions: &[Position]) -> Option<(String, Vec<Position>)> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut value = String::with_capacity(body.len());
+    let mut offsets = Vec::with_capacity(chars.len() + 1);
+    let mut i = 0;
+    while i < chars.len() {
+        let pos = positions[i];
+        if chars[i] != '\\' {
+            value.push(chars[i]);
+            offsets.push(pos);
+            i += 1;
+            continue;
+        }
+
+        let (c, len) = match chars.get(i + 1)? {
+            'n' => (Some('\n'), 2),
+            'r' => (Some('\r'), 2),
+            't' => (Some('\t'), 2),
+            '\\' => (Some('\\'), 2),
+            '0' => (Some('\0'), 2),
+            '\'' => (Some('\''), 2),
+            '"' => (Some('"'), 2),
+            'x' => {
+                let hex: String = chars.get(i + 2..i + 4)?.iter().collect();
+                (Some(char::from(u8::from_str_radix(&hex, 16).ok()?)), 4)
+            }
+            'u' => {
+                let close = chars[i..].iter().position(|c| *c == '}')?;
+                let hex: String = chars[i + 3..i + close].iter().collect();
+                (
+                    Some(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?),
+                    close + 1,
+                )
+            }
+            // line continuation: skip the newline and the leading whitespace of the next line
+            '\n' => {
+                let skipped = chars[i + 1..]
+                    .iter()
+                    .take_while(|c| c.is_whitespace())
+                    .count();
+                (None, 1 + skipped)
+            }
+            _ => return None,
+        };
+        if let Some(c) = c {
+            value.push(c);
+            offsets.push(pos);
+        }
+        i += len;
+    }
+    offsets.push(positions[chars.len()]);
+
+    Some((value, offsets))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parse_raw_string_with_hashes() {
+        let literal = SqlLiteral::parse(
+            "r##\"SELECT \"#id\"\nFROM todos\"##",
+            Position {
+                line: 3,
+                character: 8,
+            },
+        )
+        .unwrap();
+        assert_eq!(literal.kind, LiteralKind::RawStr { hashes: 2 });
+        assert_eq!(literal.value, "SELECT \"#id\"\nFROM todos");
+        assert_eq!(
+            literal.position_at(0),
+            Position {
+                line: 3,
+                character: 12,
+            }
+        );
+        // "todos"
+        assert_eq!(
+            literal.range_of(18, 23),
+            Range {
+                start: Position {
+                    line: 4,
+                    character: 5,
+                },
+                end: Position {
+                    line: 4,
+                    character: 10,
+                },
+            }
+        );
+        assert_eq!(
+            literal.range.end,
+            Position {
+                line: 4,
+                character: 13,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_string_with_escapes() {
+        let literal = SqlLiteral::parse(
+            "\"SELECT \\\"id\\\" \\\n    FROM todos\\n\"",
+            Position {
+                line: 0,
+                character: 0,
+            },
+        )
+        .unwrap();
+        assert_eq!(literal.kind, LiteralKind::Str);
+        assert_eq!(literal.value, "SELECT \"id\" FROM todos\n");
+        // `"` of `"id"` starts at the backslash
+        assert_eq!(
+            literal.position_at(7),
+            Position {
+                line: 0,
+                character: 8,
+            }
+        );
+        // "FROM" is on the next source line after the line continuation
+        assert_eq!(
+            literal.position_at(12),
+            Position {
+                line: 1,
+                character: 4,
+            }
+        );
+        assert_eq!(
+            literal.offset_at(&Position {
+                line: 1,
+                character: 6,
+            }),
+            Some(14)
+        );
+        assert_eq!(
+            literal.offset_at(&Position {
+                line: 0,
+                character: 0,
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn not_a_string_literal() {
+        let start = Position {
+            line: 0,
+            character: 0,
+        };
+        assert!(SqlLiteral::parse("1", start).is_none());
+        assert!(SqlLiteral::parse("b\"SELECT 1\"", start).is_none());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::diagnostic::{Diagnostic, FileDiagnostic, Severity};
+use crate::dialect::Dialect;
+use crate::workspace::{rust_files, SourceFile};
+use crate::{extract_sql_nodes, Config, Range, SqlNode};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// sqlx macros whose query string is hashed into `.sqlx/query-<hash>.json`
+const SQLX_QUERY_MACROS: [&str; 6] = [
+    "query",
+    "query_as",
+    "query_scalar",
+    "query_unchecked",
+    "query_as_unchecked",
+    "query_scalar_unchecked",
+];
+
+/// Same hash as sqlx-macros: hex encoded SHA-256 of the query string.
+pub fn query_hash(query: &str) -> String {
+    hex::encode(Sha256::digest(query.as_bytes()))
+}
+
+/// Metadata written by `cargo sqlx prepare` for one query.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PreparedQuery {
+    pub path: String,
+    pub hash: String,
+    pub db_name: Option<String>,
+    pub query: String,
+    /// ex) ["Text"], "unknown" when only the number of parameters is known (SQLite)
+    pub parameters: Vec<String>,
+    pub columns: Vec<PreparedColumn>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct PreparedColumn {
+    pub name: String,
+    pub type_info: String, // ex) "Int8"
+    pub nullable: Option<bool>,
+}
+
+// .sqlx/query-<hash>.json
+#[derive(Deserialize)]
+struct QueryDataFile {
+    db_name: Option<String>,
+    query: String,
+    describe: Describe,
+}
+
+#[derive(Deserialize)]
+struct Describe {
+    columns: Vec<DescribeColumn>,
+    // {"Left": ["Text"]} or {"Right": 1}
+    parameters: Option<serde_json::Value>,
+    #[serde(default)]
+    nullable: Vec<Option<bool>>,
+}
+
+#[derive(Deserialize)]
+struct DescribeColumn {
+    name: String,
+    // "Int8" for PostgreSQL and SQLite, {"type": "LongLong", ...} for MySQL
+    type_info: serde_json::Value,
+}
+
+fn type_name(type_info: &serde_json::Value) -> String {
+    match type_info {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Object(o) => o
+            .get("type")
+            .and_then(|t| t.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        _ => "unknown".to_string(),
+    }
+}
+
+impl PreparedQuery {
+    pub fn from_json(path: &str, json: &str) -> Result<PreparedQuery, serde_json::Error> {
+        let data: QueryDataFile = serde_json::from_str(json)?;
+        let parameters = match &data.describe.parameters {
+            Some(serde_json::Value::Object(o)) => match (o.get("Left"), o.get("Right")) {
+                (Some(serde_json::Value::Array(types)), _) => types.iter().map(type_name).collect(),
+                (_, Some(count)) => {
+                    vec!["unknown".to_string(); count.as_u64().unwrap_or(0) as usize]
+                }
+                _ => vec![],
+            },
+            _ => vec![],
+        };
+        let columns = data
+            .describe
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, c)| PreparedColumn {
+                name: c.name.clone(),
+                type_info: type_name(&c.type_info),
+                nullable: data.describe.nullable.get(i).copied().flatten(),
+            })
+            .collect();
+
+        Ok(PreparedQuery {
+            path: path.to_string(),
+            hash: query_hash(&data.query),
+            db_name: data.db_name,
+            query: data.query,
+            parameters,
+            columns,
+        })
+    }
+}
+
+/// The `.sqlx` directory of a workspace.
+#[derive(Debug, Clone, Default)]
+pub struct OfflineCache {
+    pub queries: Vec<PreparedQuery>,
+}
+
+impl OfflineCache {
+    /// Read `query-*.json` files. Files that cannot be read are reported and skipped.
+    pub fn load(dir: &Path) -> std::io::Result<(OfflineCache, Vec<FileDiagnostic>)> {
+        let mut cache = OfflineCache::default();
+        let mut errors = Vec::new();
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                let file_name = p.file_name().unwrap_or_default().to_string_lossy();
+                file_name.starts_with("query-") && file_name.ends_with(".json")
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            let path_str = path.to_string_lossy().to_string();
+            match PreparedQuery::from_json(&path_str, &std::fs::read_to_string(&path)?) {
+                Ok(query) => cache.queries.push(query),
+                Err(err) => errors.push(FileDiagnostic {
+                    path: path_str,
+                    diagnostic: Diagnostic {
+                        range: Range::default(),
+                        severity: Severity::Warning,
+                        code: "sqlx-offline-invalid".to_string(),
+                        message: format!("cannot read sqlx offline data: {}", err),
+                    },
+                }),
+            }
+        }
+        Ok((cache, errors))
+    }
+
+    /// Files that are not valid query metadata are skipped.
+    pub fn from_files(files: &[SourceFile]) -> OfflineCache {
+        OfflineCache {
+            queries: files
+                .iter()
+                .filter_map(|f| PreparedQuery::from_json(&f.path, &f.content).ok())
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&PreparedQuery> {
+        self.queries.iter().find(|q| q.hash == hash)
+    }
+}
+
+/// Whether sqlx checks the node at compile time and caches it in offline mode.
+pub fn is_sqlx_query(sql_node: &SqlNode) -> bool {
+    sql_node.call.is_macro && SQLX_QUERY_MACROS.contains(&sql_node.call.function_name())
+}
+
+/// Attach the cached metadata to each sqlx macro query, reporting queries that have none.
+pub fn link(sql_nodes: &mut [SqlNode], cache: &OfflineCache) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for sql_node in sql_nodes.iter_mut().filter(|n| is_sqlx_query(n)) {
+        sql_node.offline = cache.get(&query_hash(&sql_node.literal.value)).cloned();
+        if sql_node.offline.is_none() {
+            diagnostics.push(Diagnostic {
+                range: sql_node.literal.body_range(),
+                severity: Severity::Warning,
+                code: "sqlx-offline-missing".to_string(),
+                message: "no prepared metadata for this query, run `cargo sqlx prepare`"
+                    .to_string(),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Cache entries that no query of the workspace uses anymore.
+pub fn orphaned_entries(sql_nodes: &[SqlNode], cache: &OfflineCache) -> Vec<FileDiagnostic> {
+    let hashes: Vec<String> = sql_nodes
+        .iter()
+        .filter(|n| is_sqlx_query(n))
+        .map(|n| query_hash(&n.literal.value))
+        .collect();
+    cache
+        .queries
+        .iter()
+        .filter(|q| !hashes.contains(&q.hash))
+        .map(|q| FileDiagnostic {
+            path: q.path.clone(),
+            diagnostic: Diagnostic {
+                range: Range::default(),
+                severity: Severity::Warning,
+                code: "sqlx-offline-orphaned".to_string(),
+                message: "orphaned cache entry, no query of the workspace uses it".to_string(),
+            },
+        })
+        .collect()
+}
+
+/// Check every Rust file under `root` against `root/.sqlx`.
+/// Nothing is reported when the workspace does not use offline mode.
+pub fn check_workspace(root: &Path, configs: &[Config]) -> std::io::Result<Vec<FileDiagnostic>> {
+    let cache_dir = root.join(".sqlx");
+    if !cache_dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let (cache, mut diagnostics) = OfflineCache::load(&cache_dir)?;
+
+    let mut all_nodes = Vec::new();
+    for file in rust_files(root)? {
+        let mut sql_nodes = match extract_sql_nodes(
+            &std::fs::read_to_string(&file)?,
+            configs,
+            Dialect::default(),
+        ) {
+            Ok(sql_nodes) => sql_nodes,
+            Err(_) => continue,
+        };
+        let path = file.to_string_lossy().to_string();
+        diagnostics.extend(link(&mut sql_nodes, &cache).into_iter().map(|diagnostic| {
+            FileDiagnostic {
+                path: path.clone(),
+                diagnostic,
+            }
+        }));
+        all_nodes.extend(sql_nodes);
+    }
+    diagnostics.extend(orphaned_entries(&all_nodes, &cache));
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::default_configs;
+    use pretty_assertions::assert_eq;
+
+    const PREPARED_INSERT: &str = r#"{
+  "db_name": "PostgreSQL",
+  "query": "INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id",
+  "describe": {
+    "columns": [
+      {
+        "ordinal": 0,
+        "name": "id",
+        "type_info": "Int8"
+      }
+    ],
+    "parameters": {
+      "Left": [
+        "Text"
+      ]
+    },
+    "nullable": [
+      false
+    ]
+  },
+  "hash": "0a6a4f6ad5dba5c7b3c3d9f1c6e1b0e2a6e1a1a9b3c4d5e6f708192a3b4c5d6e"
+}"#;
+
+    #[test]
+    fn hash_like_sqlx() {
+        assert_eq!(
+            query_hash("SELECT 1"),
+            "e004ebd5b5532a4b85984a62f8ad48a81aa3460c1ca07701f386135d72cdecf5"
+        );
+    }
+
+    #[test]
+    fn link_cached_metadata_and_report_missing() {
+        let query = PreparedQuery::from_json(".sqlx/query-a.json", PREPARED_INSERT).unwrap();
+        assert_eq!(
+            query.columns,
+            vec![PreparedColumn {
+                name: "id".to_string(),
+                type_info: "Int8".to_string(),
+                nullable: Some(false),
+            }]
+        );
+        let cache = OfflineCache {
+            queries: vec![
+                query,
+                PreparedQuery::from_json(
+                    ".sqlx/query-b.json",
+                    r#"{"query": "SELECT 2", "describe": {"columns": [], "parameters": {"Right": 1}}}"#,
+                )
+                .unwrap(),
+            ],
+        };
+
+        let mut sql_nodes = extract_sql_nodes(
+            r#"
+async fn add_todo(pool: &PgPool, description: String) -> anyhow::Result<i64> {
+    let rec = sqlx::query!("INSERT INTO todos ( description ) VALUES ( $1 ) RETURNING id",
+        description
+    );
+    let rec = sqlx::query!("INSERT INTO todos (description) VALUES ($1) RETURNING id",
+        description
+    );
+}
+            "#,
+            &default_configs(),
+            Dialect::default(),
+        )
+        .unwrap();
+        let diagnostics = link(&mut sql_nodes, &cache);
+
+        assert_eq!(
+            sql_nodes[0].offline.as_ref().unwrap().parameters,
+            vec!["Text"]
+        );
+        assert!(sql_nodes[1].offline.is_none());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "sqlx-offline-missing");
+        assert_eq!(diagnostics[0].range, sql_nodes[1].literal.body_range());
+
+        let orphaned = orphaned_entries(&sql_nodes, &cache);
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].path, ".sqlx/query-b.json");
+    }
+}
@@ -0,0 +1,225 @@
+use crate::dialect::Dialect;
+use crate::workspace::{read_rust_files, SourceFile};
+use crate::{extract_sql_nodes, Config, Range};
+use serde::Serialize;
+use sqlparser::dialect::PostgreSqlDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// A row of a `pg_stat_statements` export.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatsRow {
+    pub query: String,
+    /// the other columns, ex) "calls", "mean_exec_time"
+    pub stats: BTreeMap<String, String>,
+}
+
+/// A stats row with the call sites that issue its query.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatsMatch {
+    #[serde(flatten)]
+    pub row: StatsRow,
+    pub call_sites: Vec<StatsCallSite>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StatsCallSite {
+    pub path: String,
+    pub line: usize, // 1-indexed, as printed in `file:line`
+    pub range: Range,
+    /// ex) "list_todos", "TodoRepository::list"
+    pub function: Option<String>,
+    /// the query as `pg_stat_statements` records it
+    pub normalized: String,
+}
+
+/// The query text `pg_stat_statements` records for `sql`: constants replaced with `$N`,
+/// numbered after the parameters the query already has, and the rest kept as written.
+/// `None` if the SQL cannot be tokenized.
+pub fn normalize(sql: &str) -> Option<String> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize().ok()?;
+    let mut next_param = tokens
+        .iter()
+        .filter_map(|token| match token {
+            Token::Placeholder(p) => p.strip_prefix('$')?.parse::<usize>().ok(),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let mut normalized = String::new();
+    for token in &tokens {
+        match token {
+            Token::Number(..)
+            | Token::SingleQuotedString(_)
+            | Token::DollarQuotedString(_)
+            | Token::SingleQuotedByteStringLiteral(_)
+            | Token::NationalStringLiteral(_)
+            | Token::EscapedStringLiteral(_)
+            | Token::UnicodeStringLiteral(_)
+            | Token::HexStringLiteral(_) => {
+                next_param += 1;
+                normalized.push_str(&format!("${}", next_param));
+            }
+            Token::EOF => {}
+            _ => normalized.push_str(&token.to_string()),
+        }
+    }
+    // the terminating `;` is not part of the recorded statement
+    Some(
+        normalized
+            .trim()
+            .trim_end_matches(';')
+            .trim_end()
+            .to_string(),
+    )
+}
+
+/// Rows of a `pg_stat_statements` CSV export, which must have a `query` column.
+pub fn read_stats_csv(csv_txt: &str) -> Result<Vec<StatsRow>, String> {
+    let mut reader = csv::Reader::from_reader(csv_txt.as_bytes());
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let query_column = headers
+        .iter()
+        .position(|h| h == "query")
+        .ok_or_else(|| "no `query` column in the stats CSV".to_string())?;
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            Ok(StatsRow {
+                query: record.get(query_column).unwrap_or_default().to_string(),
+                stats: headers
+                    .iter()
+                    .zip(record.iter())
+                    .enumerate()
+                    .filter(|(i, _)| *i != query_column)
+                    .map(|(_, (h, v))| (h.to_string(), v.to_string()))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+/// Find the call sites of `sources` that issue the query of each row. Only PostgreSQL queries
+/// are matched, and files that cannot be parsed are skipped.
+pub fn join_stats(
+    rows: Vec<StatsRow>,
+    sources: &[SourceFile],
+    configs: &[Config],
+    dialect: Dialect,
+) -> Vec<StatsMatch> {
+    let mut call_sites: Vec<(String, StatsCallSite)> = Vec::new();
+    for source in sources {
+        let sql_nodes = match extract_sql_nodes(&source.content, configs, dialect) {
+            Ok(sql_nodes) => sql_nodes,
+            Err(_) => continue,
+        };
+        for sql_node in sql_nodes {
+            if sql_node.dialect != Dialect::PostgreSql {
+                continue;
+            }
+            let normalized = match normalize(&sql_node.literal.value) {
+                Some(normalized) => normalized,
+                None => continue,
+            };
+            call_sites.push((
+                collapse_whitespace(&normalized),
+                StatsCallSite {
+                    path: source.path.clone(),
+                    line: sql_node.call.range.start.line + 1,
+                    range: sql_node.call.range,
+                    function: sql_node.call.enclosing_fn.clone(),
+                    normalized,
+                },
+            ));
+        }
+    }
+
+    rows.into_iter()
+        .map(|row| {
+            // exports are usually already normalized, but may come from a client that inlined values
+            let key = collapse_whitespace(&normalize(&row.query).unwrap_or(row.query.clone()));
+            StatsMatch {
+                call_sites: call_sites
+                    .iter()
+                    .filter(|(k, _)| *k == key)
+                    .map(|(_, call_site)| call_site.clone())
+                    .collect(),
+                row,
+            }
+        })
+        .collect()
+}
+
+/// `join_stats` for the Rust files under `root`.
+pub fn join_stats_in_workspace(
+    rows: Vec<StatsRow>,
+    root: &Path,
+    configs: &[Config],
+    dialect: Dialect,
+) -> std::io::Result<Vec<StatsMatch>> {
+    Ok(join_stats(rows, &read_rust_files(root)?, configs, dialect))
+}
+
+// the export may be reformatted by the tool that wrote it
+fn collapse_whitespace(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::default_configs;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn normalize_like_pg_stat_statements() {
+        assert_eq!(
+            normalize("SELECT id FROM todos WHERE done = $1 AND title <> '' LIMIT 10;").unwrap(),
+            "SELECT id FROM todos WHERE done = $1 AND title <> $2 LIMIT $3"
+        );
+        assert_eq!(
+            normalize("select \"Id\"\n  from todos\n  where id = 1").unwrap(),
+            "select \"Id\"\n  from todos\n  where id = $1"
+        );
+    }
+
+    #[test]
+    fn join_stats_csv_to_call_sites() {
+        let rows = read_stats_csv(
+            "calls,mean_exec_time,query\n\
+             120,3.5,\"SELECT id, description FROM todos WHERE done = $1 LIMIT $2\"\n\
+             1,0.1,SELECT 1\n",
+        )
+        .unwrap();
+        let sources = vec![SourceFile {
+            path: "src/repository.rs".to_string(),
+            content: r#"
+impl TodoRepository {
+    async fn list(&self, done: bool) {
+        sqlx::query!(
+            "SELECT id, description
+             FROM todos WHERE done = $1 LIMIT 50",
+            done
+        );
+    }
+}
+"#
+            .to_string(),
+        }];
+        let result = join_stats(rows, &sources, &default_configs(), Dialect::PostgreSql);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].row.stats["calls"], "120");
+        assert_eq!(result[0].call_sites.len(), 1);
+        assert_eq!(result[0].call_sites[0].path, "src/repository.rs");
+        assert_eq!(result[0].call_sites[0].line, 4);
+        assert_eq!(
+            result[0].call_sites[0].function.as_deref(),
+            Some("TodoRepository::list")
+        );
+        assert_eq!(result[1].call_sites, vec![]);
+
+        assert!(read_stats_csv("calls\n1\n").is_err());
+    }
+}
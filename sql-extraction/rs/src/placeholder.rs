@@ -0,0 +1,450 @@
+use crate::edit::{text_of, TextEdit};
+use crate::literal::offset_of_location;
+use crate::{span_range, CallSite, Range, SqlNode};
+use serde::Deserialize;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::str::FromStr;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+
+/// Bind parameter syntax of a query.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderStyle {
+    /// `$1`, `$2`, ... (PostgreSQL)
+    Dollar,
+    /// `?` (MySQL, SQLite)
+    Question,
+}
+
+impl FromStr for PlaceholderStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dollar" | "$" => Ok(PlaceholderStyle::Dollar),
+            "question" | "?" => Ok(PlaceholderStyle::Question),
+            _ => Err(format!(
+                "unknown placeholder style '{}', expected one of dollar, question",
+                s
+            )),
+        }
+    }
+}
+
+/// A `.bind(x)` of the method chain on a query function call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindCall {
+    /// from the `.` to the closing `)`
+    pub range: Range,
+    /// range of `x`
+    pub arg_range: Range,
+}
+
+/// Edits rewriting the placeholders of a node to `style`, with the bind arguments of the
+/// macro or the `.bind()` chain reordered so every placeholder still gets its value:
+/// `$N` used twice becomes two `?` with the value bound twice, and `?` bound twice to the
+/// same variable becomes one `$N`. A value bound twice to `.bind()` is borrowed, ex)
+/// `.bind(&name).bind(&name)`. Empty if the query already uses `style`, `None` if the binds
+/// do not match the placeholders, a value is not used, or an expression would be bound twice
+/// and run twice, ex) `next_id()`.
+pub fn convert_placeholders(
+    source_txt: &str,
+    sql_node: &SqlNode,
+    style: PlaceholderStyle,
+) -> Option<Vec<TextEdit>> {
+    let placeholders = placeholders(&sql_node.literal.value)?;
+    if placeholders.is_empty() || placeholders.iter().all(|p| p.style() == style) {
+        return Some(vec![]);
+    }
+    if placeholders.iter().any(|p| p.style() == style) {
+        // mixed styles cannot be numbered reliably
+        return None;
+    }
+    let binds = bind_args(source_txt, &sql_node.call)?;
+    let bind_texts: Vec<String> = binds.iter().map(|r| text_of(source_txt, r)).collect();
+
+    // index into `bind_texts` of each new bind, and the new text of each placeholder
+    let mut new_binds: Vec<usize> = Vec::new();
+    let mut new_placeholders: Vec<String> = Vec::new();
+    match style {
+        PlaceholderStyle::Question => {
+            for placeholder in &placeholders {
+                let bind = placeholder.number?.checked_sub(1)?;
+                bind_texts.get(bind)?;
+                new_binds.push(bind);
+                new_placeholders.push("?".to_string());
+            }
+            let uses = |bind: usize| new_binds.iter().filter(|&&b| b == bind).count();
+            if bind_texts
+                .iter()
+                .enumerate()
+                .any(|(bind, text)| uses(bind) == 0 || (uses(bind) > 1 && !is_plain_value(text)))
+            {
+                return None;
+            }
+        }
+        PlaceholderStyle::Dollar => {
+            if placeholders.len() != bind_texts.len() {
+                return None;
+            }
+            for (bind, text) in bind_texts.iter().enumerate() {
+                let reused = new_binds
+                    .iter()
+                    .position(|&b| is_plain_value(text) && bind_texts[b] == *text);
+                let number = match reused {
+                    Some(number) => number,
+                    None => {
+                        new_binds.push(bind);
+                        new_binds.len() - 1
+                    }
+                };
+                new_placeholders.push(format!("${}", number + 1));
+            }
+        }
+    }
+
+    let mut edits: Vec<TextEdit> = placeholders
+        .iter()
+        .zip(new_placeholders)
+        .map(|(placeholder, new_text)| TextEdit {
+            range: sql_node
+                .literal
+                .range_of(placeholder.start, placeholder.end),
+            new_text,
+        })
+        .collect();
+    let new_texts: Vec<String> = new_binds
+        .iter()
+        .map(|&bind| {
+            let text = &bind_texts[bind];
+            let repeated = new_binds.iter().filter(|&&b| b == bind).count() > 1;
+            // `.bind()` takes its value, the macros borrow theirs
+            if repeated && !sql_node.call.is_macro && !text.starts_with('&') {
+                format!("&{}", text)
+            } else {
+                text.clone()
+            }
+        })
+        .collect();
+    if new_texts != bind_texts {
+        edits.push(rebind_edit(source_txt, &sql_node.call, &binds, &new_texts)?);
+    }
+    Some(edits)
+}
+
+struct Placeholder {
+    number: Option<usize>, // N of `$N`, `None` for `?`
+    start: usize,
+    end: usize,
+}
+
+impl Placeholder {
+    fn style(&self) -> PlaceholderStyle {
+        match self.number {
+            Some(_) => PlaceholderStyle::Dollar,
+            None => PlaceholderStyle::Question,
+        }
+    }
+}
+
+fn placeholders(sql: &str) -> Option<Vec<Placeholder>> {
+    let tokens = Tokenizer::new(&GenericDialect {}, sql)
+        .tokenize_with_location()
+        .ok()?;
+    Some(
+        tokens
+            .iter()
+            .filter_map(|token| match &token.token {
+                Token::Placeholder(p) if p == "?" || p.starts_with('$') => {
+                    let start =
+                        offset_of_location(sql, token.span.start.line, token.span.start.column);
+                    Some(Placeholder {
+                        number: p.strip_prefix('$').and_then(|n| n.parse().ok()),
+                        start,
+                        end: start + p.chars().count(),
+                    })
+                }
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
+/// Ranges of the values bound to the query of `call`: the macro arguments after the SQL,
+/// or the arguments of the `.bind()` chain.
+fn bind_args(source_txt: &str, call: &CallSite) -> Option<Vec<Range>> {
+    if call.is_macro {
+        return Some(
+            call.args[call.sql_arg_no + 1..]
+                .iter()
+                .map(|arg| arg.range)
+                .collect(),
+        );
+    }
+    Some(
+        bind_chain(source_txt, call)?
+            .into_iter()
+            .map(|bind| bind.arg_range)
+            .collect(),
+    )
+}
+
+// a single edit replacing all the binds with `texts`, keeping the separator of the source
+fn rebind_edit(
+    source_txt: &str,
+    call: &CallSite,
+    binds: &[Range],
+    texts: &[String],
+) -> Option<TextEdit> {
+    if call.is_macro {
+        let sql_arg = &call.args[call.sql_arg_no];
+        let first = binds.first()?;
+        // ex) ",\n            " between the SQL and the first value
+        let separator = text_of(
+            source_txt,
+            &Range {
+                start: sql_arg.range.end,
+                end: first.start,
+            },
+        );
+        return Some(TextEdit {
+            range: Range {
+                start: first.start,
+                end: binds.last()?.end,
+            },
+            new_text: texts.join(&separator),
+        });
+    }
+    let chain = bind_chain(source_txt, call)?;
+    let separator = text_of(
+        source_txt,
+        &Range {
+            start: call.range.end,
+            end: chain.first()?.range.start,
+        },
+    );
+    Some(TextEdit {
+        range: Range {
+            start: chain.first()?.range.start,
+            end: chain.last()?.range.end,
+        },
+        new_text: texts
+            .iter()
+            .map(|text| format!(".bind({})", text))
+            .collect::<Vec<_>>()
+            .join(&separator),
+    })
+}
+
+// a variable or field, binding it twice gives the same value
+fn is_plain_value(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '.' | '&' | ':'))
+}
+
+/// The `.bind()` calls chained directly on the function call of `call`, in order.
+/// `None` if the source cannot be parsed, or if a `.bind()` comes after another method,
+/// ex) `.persistent(false).bind(x)`, so that the direct ones are not all the binds.
+pub(crate) fn bind_chain(source_txt: &str, call: &CallSite) -> Option<Vec<BindCall>> {
+    let ast = syn::parse_file(source_txt).ok()?;
+    let mut visitor = BindChainVisitor {
+        call_range: call.range,
+        found: false,
+        binds: None,
+    };
+    visitor.visit_file(&ast);
+    match visitor.found {
+        true => visitor.binds,
+        false => Some(vec![]),
+    }
+}
+
+struct BindChainVisitor {
+    call_range: Range,
+    /// the methods chained on the call were seen
+    found: bool,
+    binds: Option<Vec<BindCall>>,
+}
+
+impl<'ast> Visit<'ast> for BindChainVisitor {
+    fn visit_expr_method_call(&mut self, method_call: &'ast syn::ExprMethodCall) {
+        if self.found {
+            return;
+        }
+        // the outermost method of a chain is visited first, walk down to the call
+        let mut methods = vec![method_call];
+        let mut expr = method_call;
+        while let syn::Expr::MethodCall(receiver) = &*expr.receiver {
+            methods.push(receiver);
+            expr = receiver;
+        }
+        if span_range(expr.receiver.span()) != self.call_range {
+            visit::visit_expr_method_call(self, method_call);
+            return;
+        }
+        self.found = true;
+        methods.reverse();
+        let is_bind = |m: &&syn::ExprMethodCall| m.method == "bind";
+        let direct = methods
+            .iter()
+            .take_while(|m| is_bind(m) && m.args.len() == 1)
+            .count();
+        if methods[direct..].iter().any(is_bind) {
+            return;
+        }
+        self.binds = Some(
+            methods[..direct]
+                .iter()
+                .map(|bind| BindCall {
+                    range: Range {
+                        start: span_range(bind.dot_token.span()).start,
+                        end: span_range(bind.paren_token.span.join()).end,
+                    },
+                    arg_range: span_range(bind.args[0].span()),
+                })
+                .collect(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{extract_sql_nodes, Config};
+    use pretty_assertions::assert_eq;
+
+    fn apply(source_txt: &str, edits: &[TextEdit]) -> String {
+        let mut lines: Vec<String> = source_txt.split('\n').map(String::from).collect();
+        for edit in edits.iter().rev() {
+            let start = &lines[edit.range.start.line];
+            let end = &lines[edit.range.end.line];
+            let replaced = format!(
+                "{}{}{}",
+                start
+                    .chars()
+                    .take(edit.range.start.character)
+                    .collect::<String>(),
+                edit.new_text,
+                end.chars()
+                    .skip(edit.range.end.character)
+                    .collect::<String>()
+            );
+            lines.splice(
+                edit.range.start.line..=edit.range.end.line,
+                replaced.split('\n').map(String::from).collect::<Vec<_>>(),
+            );
+        }
+        lines.join("\n")
+    }
+
+    fn convert(source_txt: &str, configs: &[Config], style: PlaceholderStyle) -> String {
+        let sql_nodes = extract_sql_nodes(source_txt, configs, Dialect::Generic).unwrap();
+        let edits = convert_placeholders(source_txt, &sql_nodes[0], style).unwrap();
+        apply(source_txt, &edits)
+    }
+
+    #[test]
+    fn dollar_to_question_in_macro() {
+        let source_txt = r#"
+fn find(pool: &Pool, id: i64, title: &str) {
+    sqlx::query!(
+        "SELECT id FROM todos WHERE title = $2 AND (id = $1 OR parent_id = $1)",
+        id,
+        title
+    );
+}
+"#;
+        let result = convert(
+            source_txt,
+            &crate::default_configs(),
+            PlaceholderStyle::Question,
+        );
+        assert_eq!(
+            result,
+            r#"
+fn find(pool: &Pool, id: i64, title: &str) {
+    sqlx::query!(
+        "SELECT id FROM todos WHERE title = ? AND (id = ? OR parent_id = ?)",
+        title,
+        id,
+        id
+    );
+}
+"#
+        );
+
+        // and back, the duplicated `id` is bound once
+        assert_eq!(
+            convert(&result, &crate::default_configs(), PlaceholderStyle::Dollar),
+            r#"
+fn find(pool: &Pool, id: i64, title: &str) {
+    sqlx::query!(
+        "SELECT id FROM todos WHERE title = $1 AND (id = $2 OR parent_id = $2)",
+        title,
+        id
+    );
+}
+"#
+        );
+    }
+
+    #[test]
+    fn dollar_to_question_in_bind_chain() {
+        let configs = vec![Config {
+            functionName: "query".to_string(),
+            sqlArgNo: 0,
+            isMacro: false,
+            dialect: None,
+        }];
+        let source_txt = r#"
+async fn find(pool: &Pool, id: i64) {
+    sqlx::query("SELECT id FROM todos WHERE id = $1 OR parent_id = $1")
+        .bind(id)
+        .fetch_all(pool)
+        .await;
+}
+"#;
+        assert_eq!(
+            convert(source_txt, &configs, PlaceholderStyle::Question),
+            r#"
+async fn find(pool: &Pool, id: i64) {
+    sqlx::query("SELECT id FROM todos WHERE id = ? OR parent_id = ?")
+        .bind(&id)
+        .bind(&id)
+        .fetch_all(pool)
+        .await;
+}
+"#
+        );
+
+        // an owned value is borrowed to be bound twice
+        let source_txt = r#"fn f(name: String) { sqlx::query("SELECT $1, $1").bind(name); }"#;
+        assert_eq!(
+            convert(source_txt, &configs, PlaceholderStyle::Question),
+            r#"fn f(name: String) { sqlx::query("SELECT ?, ?").bind(&name).bind(&name); }"#
+        );
+
+        let refused = |source_txt: &str| {
+            let sql_nodes = extract_sql_nodes(source_txt, &configs, Dialect::Generic).unwrap();
+            convert_placeholders(source_txt, &sql_nodes[0], PlaceholderStyle::Question)
+        };
+        // `f()` would run twice
+        assert_eq!(
+            refused(r#"fn f() { sqlx::query("SELECT $1, $1").bind(f()); }"#),
+            None
+        );
+        // `$2` has no value
+        assert_eq!(
+            refused(r#"fn f() { sqlx::query("SELECT $2").bind(id); }"#),
+            None
+        );
+        // `b` is not used, it would be dropped
+        assert_eq!(
+            refused(r#"fn f() { sqlx::query("SELECT $1").bind(a).bind(b); }"#),
+            None
+        );
+    }
+}
@@ -0,0 +1,380 @@
+use crate::dialect::Dialect;
+use crate::format::FormatOptions;
+use crate::lint::LintConfig;
+use crate::workspace::{rust_files, SourceFile};
+use crate::Config;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+pub const CONFIG_FILE: &str = "sqlsurge.toml";
+
+/// Query functions of a library, instead of listing them one by one.
+#[derive(Deserialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preset {
+    /// `sqlx::query!`, `sqlx::query_as!`, ... checked at compile time
+    Sqlx,
+    /// `sqlx::query`, `sqlx::query_as`, ... with `.bind()`
+    SqlxRuntime,
+    /// `diesel::sql_query`
+    Diesel,
+}
+
+impl Preset {
+    pub fn configs(&self) -> Vec<Config> {
+        let config = |name: &str, sql_arg_no: usize, is_macro: bool| Config {
+            functionName: name.to_string(),
+            sqlArgNo: sql_arg_no,
+            isMacro: is_macro,
+            dialect: None,
+        };
+        match self {
+            Preset::Sqlx => vec![
+                config("query", 0, true),
+                config("query_as", 1, true),
+                config("query_scalar", 0, true),
+                config("query_unchecked", 0, true),
+                config("query_as_unchecked", 1, true),
+                config("query_scalar_unchecked", 0, true),
+            ],
+            Preset::SqlxRuntime => vec![
+                config("query", 0, false),
+                config("query_as", 0, false),
+                config("query_scalar", 0, false),
+            ],
+            Preset::Diesel => vec![config("sql_query", 0, false)],
+        }
+    }
+}
+
+/// Settings of `sqlsurge.toml`, `[workspace.metadata.sqlsurge]` or `[package.metadata.sqlsurge]`
+/// of `Cargo.toml`, with what is not set left to the outer files or the defaults.
+///
+/// ```toml
+/// presets = ["sqlx", "sqlx-runtime"]
+/// dialect = "postgresql"
+/// migrations = "db/migrations"
+///
+/// [[matchers]]
+/// functionName = "fetch_all"
+/// sqlArgNo = 1
+/// isMacro = false
+///
+/// [lint.rules]
+/// keyword-case = "off"
+///
+/// [format]
+/// demoteMaxLength = 60
+/// layout = { leadingNewline = true, trailingNewline = true }
+/// ```
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// `["sqlx"]` if no file sets it
+    pub presets: Option<Vec<Preset>>,
+    /// functions and macros besides the presets, same as `sqlsurge.customRawSqlQuery`
+    pub matchers: Vec<Config>,
+    pub dialect: Option<Dialect>,
+    /// relative to the file setting it
+    pub migrations: Option<PathBuf>,
+    pub lint: LintConfig,
+    /// options of `format-check` and of formatting, replaced as a whole by an inner file
+    pub format: Option<FormatOptions>,
+}
+
+impl ProjectConfig {
+    /// Read a `sqlsurge.toml`.
+    pub fn from_toml(config_txt: &str) -> Result<ProjectConfig, String> {
+        toml::from_str(config_txt).map_err(|err| err.to_string())
+    }
+
+    /// Read the `sqlsurge` metadata of a `Cargo.toml`, the package one over the workspace one
+    /// when the manifest has both. `None` if it has neither.
+    pub fn from_cargo_toml(manifest_txt: &str) -> Result<Option<ProjectConfig>, String> {
+        let manifest: toml::Table = toml::from_str(manifest_txt).map_err(|err| err.to_string())?;
+        let metadata = |section: &str| {
+            manifest
+                .get(section)
+                .and_then(|s| s.get("metadata"))
+                .and_then(|m| m.get("sqlsurge"))
+                .map(|config| config.clone().try_into::<ProjectConfig>())
+                .transpose()
+                .map_err(|err| format!("[{}.metadata.sqlsurge] {}", section, err))
+        };
+        Ok(match (metadata("workspace")?, metadata("package")?) {
+            (Some(workspace), Some(package)) => Some(workspace.merge(package)),
+            (workspace, package) => package.or(workspace),
+        })
+    }
+
+    /// `inner` over `self`: what it sets replaces, its matchers are added, and its lint rules
+    /// replace the same rules only.
+    pub fn merge(mut self, inner: ProjectConfig) -> ProjectConfig {
+        self.matchers.extend(inner.matchers);
+        self.lint.rules.extend(inner.lint.rules);
+        ProjectConfig {
+            presets: inner.presets.or(self.presets),
+            matchers: self.matchers,
+            dialect: inner.dialect.or(self.dialect),
+            migrations: inner.migrations.or(self.migrations),
+            lint: self.lint,
+            format: inner.format.or(self.format),
+        }
+    }
+}
+
+/// Settings of the files of a directory, resolved from the configs around it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectSettings {
+    pub configs: Vec<Config>,
+    pub dialect: Dialect,
+    pub migrations: PathBuf,
+    pub lint: LintConfig,
+    pub format: FormatOptions,
+}
+
+/// Resolve the settings of a file or directory: the configs of its directory and of the
+/// ones above it, up to the workspace root, inner ones first. The crate of a workspace
+/// overrides the workspace with its own `sqlsurge.toml` or `[package.metadata.sqlsurge]`.
+/// Migrations default to `migrations` of the crate, like `sqlx::migrate!`.
+pub fn discover(path: &Path) -> Result<ProjectSettings, String> {
+    let start = if path.is_dir() {
+        path
+    } else {
+        path.parent().unwrap_or(Path::new("."))
+    };
+    let dirs: Vec<&Path> = start.ancestors().collect();
+    let manifests: Vec<Option<String>> = dirs
+        .iter()
+        .map(|dir| std::fs::read_to_string(dir.join("Cargo.toml")).ok())
+        .collect();
+    let is_workspace = |manifest: &str| {
+        toml::from_str::<toml::Table>(manifest).is_ok_and(|m| m.contains_key("workspace"))
+    };
+    // the workspace root, or the outermost crate outside of a workspace
+    let top = manifests
+        .iter()
+        .position(|m| m.as_deref().is_some_and(is_workspace))
+        .or_else(|| manifests.iter().rposition(Option::is_some))
+        .unwrap_or(0);
+    let crate_dir = manifests
+        .iter()
+        .position(Option::is_some)
+        .map_or(start, |i| dirs[i]);
+
+    let mut config = ProjectConfig::default();
+    for (dir, manifest) in dirs[..=top].iter().zip(&manifests).rev() {
+        if let Some(manifest) = manifest {
+            let cargo_config = ProjectConfig::from_cargo_toml(manifest)
+                .map_err(|err| format!("{}: {}", dir.join("Cargo.toml").display(), err))?;
+            if let Some(cargo_config) = cargo_config {
+                config = config.merge(relative_to(cargo_config, dir));
+            }
+        }
+        let config_path = dir.join(CONFIG_FILE);
+        if let Ok(config_txt) = std::fs::read_to_string(&config_path) {
+            let file_config = ProjectConfig::from_toml(&config_txt)
+                .map_err(|err| format!("{}: {}", config_path.display(), err))?;
+            config = config.merge(relative_to(file_config, dir));
+        }
+    }
+
+    Ok(ProjectSettings {
+        configs: resolve_configs(
+            &config.presets.unwrap_or(vec![Preset::Sqlx]),
+            config.matchers,
+        ),
+        dialect: config.dialect.unwrap_or_default(),
+        migrations: config
+            .migrations
+            .unwrap_or_else(|| crate_dir.join("migrations")),
+        lint: config.lint,
+        format: config.format.unwrap_or_default(),
+    })
+}
+
+/// Configs of the presets then of the matchers, one per function: a matcher repeating a preset
+/// entry or an outer matcher replaces it, so a call is not reported once per config.
+fn resolve_configs(presets: &[Preset], matchers: Vec<Config>) -> Vec<Config> {
+    let mut configs: Vec<Config> = Vec::new();
+    for config in presets.iter().flat_map(Preset::configs).chain(matchers) {
+        match configs.iter_mut().find(|c| {
+            c.functionName == config.functionName
+                && c.sqlArgNo == config.sqlArgNo
+                && c.isMacro == config.isMacro
+        }) {
+            Some(same) => *same = config,
+            None => configs.push(config),
+        }
+    }
+    configs
+}
+
+/// Rust files under `root` with the settings of the crate each one is in.
+pub fn read_workspace(root: &Path) -> Result<Vec<(SourceFile, ProjectSettings)>, String> {
+    let mut settings: HashMap<PathBuf, ProjectSettings> = HashMap::new();
+    rust_files(root)
+        .map_err(|err| format!("{}: {}", root.display(), err))?
+        .into_iter()
+        .map(|path| {
+            let dir = path.parent().unwrap_or(root).to_path_buf();
+            if !settings.contains_key(&dir) {
+                settings.insert(dir.clone(), discover(&dir)?);
+            }
+            let content = std::fs::read_to_string(&path)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            Ok((
+                SourceFile {
+                    path: path.to_string_lossy().to_string(),
+                    content,
+                },
+                settings[&dir].clone(),
+            ))
+        })
+        .collect()
+}
+
+fn relative_to(mut config: ProjectConfig, dir: &Path) -> ProjectConfig {
+    config.migrations = config.migrations.map(|m| dir.join(m));
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::LayoutStyle;
+    use crate::lint::RuleLevel;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn package_metadata_over_workspace_metadata() {
+        let config = ProjectConfig::from_cargo_toml(
+            r#"
+[package]
+name = "todos"
+
+[workspace.metadata.sqlsurge]
+presets = ["sqlx"]
+dialect = "postgresql"
+
+[workspace.metadata.sqlsurge.lint.rules]
+keyword-case = "off"
+no-select-star = "error"
+
+[package.metadata.sqlsurge]
+presets = ["sqlx-runtime", "diesel"]
+migrations = "db/migrations"
+
+[[package.metadata.sqlsurge.matchers]]
+functionName = "fetch_all"
+sqlArgNo = 1
+isMacro = false
+
+[package.metadata.sqlsurge.lint.rules]
+keyword-case = "warning"
+
+[workspace.metadata.sqlsurge.format]
+indent = "  "
+
+[package.metadata.sqlsurge.format]
+demoteMaxLength = 60
+layout = { leadingNewline = true }
+"#,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            config.presets,
+            Some(vec![Preset::SqlxRuntime, Preset::Diesel])
+        );
+        assert_eq!(config.dialect, Some(Dialect::PostgreSql));
+        assert_eq!(config.migrations, Some(PathBuf::from("db/migrations")));
+        assert_eq!(config.matchers[0].functionName, "fetch_all");
+        assert_eq!(
+            config.lint.rules.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("keyword-case".to_string(), RuleLevel::Warning),
+                ("no-select-star".to_string(), RuleLevel::Error),
+            ]
+        );
+
+        // the package options as a whole
+        assert_eq!(
+            config.format,
+            Some(FormatOptions {
+                demote_max_length: Some(60),
+                layout: LayoutStyle {
+                    leading_newline: Some(true),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+        );
+
+        assert_eq!(
+            ProjectConfig::from_cargo_toml("[package]\nname = \"todos\"\n"),
+            Ok(None)
+        );
+        assert!(ProjectConfig::from_toml("dialect = \"oracle\"")
+            .unwrap_err()
+            .contains("unknown variant `oracle`"));
+        assert!(ProjectConfig::from_toml("preset = [\"sqlx\"]")
+            .unwrap_err()
+            .contains("unknown field `preset`"));
+        assert!(ProjectConfig::from_toml("[format]\ndemoteMaxLen = 60")
+            .unwrap_err()
+            .contains("unknown field `demoteMaxLen`"));
+    }
+
+    #[test]
+    fn one_config_per_function() {
+        let matcher = |name: &str, dialect: Option<Dialect>| Config {
+            functionName: name.to_string(),
+            sqlArgNo: 0,
+            isMacro: true,
+            dialect,
+        };
+        // from the workspace and from the crate
+        let configs = resolve_configs(
+            &[Preset::Sqlx],
+            vec![
+                matcher("fetch", None),
+                matcher("query", Some(Dialect::MySql)),
+                matcher("fetch", Some(Dialect::Sqlite)),
+            ],
+        );
+        assert_eq!(configs.len(), Preset::Sqlx.configs().len() + 1);
+        assert_eq!(configs[0], matcher("query", Some(Dialect::MySql)));
+        assert_eq!(
+            configs.last(),
+            Some(&matcher("fetch", Some(Dialect::Sqlite)))
+        );
+    }
+
+    #[test]
+    fn discover_in_example_workspace() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example-workspaces/rs");
+        let settings = discover(&root.join("src/main.rs")).unwrap();
+        assert_eq!(settings.dialect, Dialect::PostgreSql);
+        assert_eq!(settings.migrations, root.join("migrations"));
+        let names: Vec<&str> = settings
+            .configs
+            .iter()
+            .map(|c| c.functionName.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "query",
+                "query_as",
+                "query_scalar",
+                "query_unchecked",
+                "query_as_unchecked",
+                "query_scalar_unchecked",
+                "sql_query",
+            ]
+        );
+    }
+}
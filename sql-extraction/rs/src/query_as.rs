@@ -0,0 +1,371 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::literal::offset_of_location;
+use crate::schema::Catalog;
+use crate::workspace::rust_files;
+use crate::SqlNode;
+use sqlparser::ast::{Expr, SelectItem, SetExpr, Spanned, Statement, TableFactor, TableWithJoins};
+use sqlparser::parser::Parser;
+use std::path::Path;
+use syn::visit::{self, Visit};
+
+/// A struct with named fields that `query_as!` can map rows to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordStruct {
+    pub name: String,
+    pub fields: Vec<String>,
+}
+
+/// A column of a query result.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputColumn {
+    /// `None` for expressions without an alias
+    pub name: Option<String>,
+    /// char offsets into the SQL
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Structs with named fields declared in a file.
+pub fn collect_structs(source_txt: &str) -> Result<Vec<RecordStruct>, syn::Error> {
+    let ast = syn::parse_file(source_txt)?;
+    let mut visitor = StructVisitor::default();
+    visitor.visit_file(&ast);
+    Ok(visitor.structs)
+}
+
+/// Structs with named fields declared in the Rust files under `root`.
+/// Files that cannot be parsed are skipped.
+pub fn collect_crate_structs(root: &Path) -> std::io::Result<Vec<RecordStruct>> {
+    let mut structs = Vec::new();
+    for file in rust_files(root)? {
+        if let Ok(s) = collect_structs(&std::fs::read_to_string(file)?) {
+            structs.extend(s);
+        }
+    }
+    Ok(structs)
+}
+
+#[derive(Default)]
+struct StructVisitor {
+    structs: Vec<RecordStruct>,
+}
+
+impl<'ast> Visit<'ast> for StructVisitor {
+    fn visit_item_struct(&mut self, item_struct: &'ast syn::ItemStruct) {
+        if let syn::Fields::Named(fields) = &item_struct.fields {
+            self.structs.push(RecordStruct {
+                name: item_struct.ident.to_string(),
+                fields: fields
+                    .named
+                    .iter()
+                    .filter_map(|f| f.ident.as_ref())
+                    // raw identifiers, ex) r#type
+                    .map(|i| i.to_string().trim_start_matches("r#").to_string())
+                    .collect(),
+            });
+        }
+        visit::visit_item_struct(self, item_struct);
+    }
+}
+
+/// Name of the record type of a `query_as!` node, ex) "Todo" for `query_as!(models::Todo, "...")`
+pub fn record_type_name(sql_node: &SqlNode) -> Option<&str> {
+    let call = &sql_node.call;
+    if !call.is_macro || !call.function_name().starts_with("query_as") || call.sql_arg_no == 0 {
+        return None;
+    }
+    call.args.first()?.text.rsplit("::").next().map(str::trim)
+}
+
+/// Compare the fields of the `query_as!` target struct with the columns of the query.
+/// `catalog` is used to expand `SELECT *`; without it such queries are not checked.
+pub fn check_query_as(
+    sql_node: &SqlNode,
+    structs: &[RecordStruct],
+    catalog: Option<&Catalog>,
+) -> Vec<Diagnostic> {
+    let record =
+        match record_type_name(sql_node).and_then(|name| structs.iter().find(|s| s.name == name)) {
+            Some(record) => record,
+            None => return vec![],
+        };
+    let columns = match output_columns(sql_node, catalog) {
+        Some(columns) => columns,
+        None => return vec![],
+    };
+
+    let mut diagnostics = Vec::new();
+    for column in &columns {
+        let range = sql_node.literal.range_of(column.start, column.end);
+        match &column.name {
+            None => diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Error,
+                code: "query-as-unnamed-column".to_string(),
+                message: format!(
+                    "column expression needs an alias to map to a field of `{}`",
+                    record.name
+                ),
+            }),
+            Some(name) if !record.fields.contains(name) => diagnostics.push(Diagnostic {
+                range,
+                severity: Severity::Error,
+                code: "query-as-extra-column".to_string(),
+                message: format!("column `{}` has no field in `{}`", name, record.name),
+            }),
+            Some(_) => {}
+        }
+    }
+    for field in &record.fields {
+        if !columns.iter().any(|c| c.name.as_ref() == Some(field)) {
+            diagnostics.push(Diagnostic {
+                range: sql_node.literal.body_range(),
+                severity: Severity::Error,
+                code: "query-as-missing-column".to_string(),
+                message: format!(
+                    "field `{}` of `{}` is not selected by the query",
+                    field, record.name
+                ),
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Columns returned by the last statement of a node, `None` if they cannot be known.
+pub fn output_columns(sql_node: &SqlNode, catalog: Option<&Catalog>) -> Option<Vec<OutputColumn>> {
+    let sql = &sql_node.literal.value;
+    let statements = Parser::parse_sql(&*sql_node.dialect.parser_dialect(), sql).ok()?;
+    let (items, from) = match statements.last()? {
+        Statement::Query(query) => select_items(&query.body)?,
+        Statement::Insert(insert) => (insert.returning.as_ref()?, vec![]),
+        Statement::Update {
+            returning, table, ..
+        } => (returning.as_ref()?, vec![table]),
+        Statement::Delete(delete) => (delete.returning.as_ref()?, vec![]),
+        _ => return None,
+    };
+
+    let offset = |location: sqlparser::tokenizer::Location| {
+        offset_of_location(sql, location.line, location.column)
+    };
+    let mut columns = Vec::new();
+    for item in items {
+        let span = item.span();
+        let (start, end) = (offset(span.start), offset(span.end));
+        match item {
+            SelectItem::UnnamedExpr(Expr::Identifier(ident)) => columns.push(OutputColumn {
+                name: Some(ident.value.clone()),
+                start,
+                end,
+            }),
+            SelectItem::UnnamedExpr(Expr::CompoundIdentifier(idents)) => {
+                columns.push(OutputColumn {
+                    name: idents.last().map(|i| i.value.clone()),
+                    start,
+                    end,
+                })
+            }
+            SelectItem::UnnamedExpr(_) => columns.push(OutputColumn {
+                name: None,
+                start,
+                end,
+            }),
+            SelectItem::ExprWithAlias { alias, .. } => columns.push(OutputColumn {
+                name: Some(sqlx_column_name(&alias.value).to_string()),
+                start,
+                end,
+            }),
+            SelectItem::Wildcard(_) => {
+                for table in table_names(&from) {
+                    for column in &catalog?.table(&table.0)?.columns {
+                        columns.push(OutputColumn {
+                            name: Some(column.name.clone()),
+                            start,
+                            end,
+                        });
+                    }
+                }
+            }
+            SelectItem::QualifiedWildcard(name, _) => {
+                let qualifier = &name.0.last()?.value;
+                let (table, _) = table_names(&from)
+                    .into_iter()
+                    .find(|(t, alias)| alias.as_ref().unwrap_or(t) == qualifier)?;
+                for column in &catalog?.table(&table)?.columns {
+                    columns.push(OutputColumn {
+                        name: Some(column.name.clone()),
+                        start,
+                        end,
+                    });
+                }
+            }
+        }
+    }
+    Some(columns)
+}
+
+/// sqlx overrides in aliases, ex) `id AS "id!"`, `done AS "done?: bool"`
+fn sqlx_column_name(alias: &str) -> &str {
+    alias.split(['!', '?', ':']).next().unwrap_or(alias).trim()
+}
+
+fn select_items(set_expr: &SetExpr) -> Option<(&Vec<SelectItem>, Vec<&TableWithJoins>)> {
+    match set_expr {
+        SetExpr::Select(select) => Some((&select.projection, select.from.iter().collect())),
+        SetExpr::Query(query) => select_items(&query.body),
+        // column names come from the left-most query
+        SetExpr::SetOperation { left, .. } => select_items(left),
+        _ => None,
+    }
+}
+
+// (table name, alias)
+fn table_names(from: &[&TableWithJoins]) -> Vec<(String, Option<String>)> {
+    from.iter()
+        .flat_map(|t| std::iter::once(&t.relation).chain(t.joins.iter().map(|j| &j.relation)))
+        .filter_map(|relation| match relation {
+            TableFactor::Table { name, alias, .. } => Some((
+                name.0.last()?.value.clone(),
+                alias.as_ref().map(|a| a.name.value.clone()),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{default_configs, extract_sql_nodes, Position, Range};
+    use pretty_assertions::assert_eq;
+
+    fn check(source_txt: &str, catalog: Option<&Catalog>) -> Vec<Diagnostic> {
+        let structs = collect_structs(source_txt).unwrap();
+        extract_sql_nodes(source_txt, &default_configs(), Dialect::Generic)
+            .unwrap()
+            .iter()
+            .flat_map(|sql_node| check_query_as(sql_node, &structs, catalog))
+            .collect()
+    }
+
+    #[test]
+    fn matching_fields_and_columns() {
+        let result = check(
+            r##"
+struct Todo {
+    id: i64,
+    description: String,
+    done: bool,
+}
+
+async fn list_todos(pool: &PgPool) -> anyhow::Result<()> {
+    let recs = sqlx::query_as!(
+        Todo,
+        r#"
+SELECT t.id, description AS "description!", done as "done: bool"
+FROM todos t
+ORDER BY id
+        "#
+    );
+    let recs = sqlx::query_as!(
+        crate::Todo,
+        "INSERT INTO todos (description) VALUES ($1) RETURNING id, description, done",
+        description
+    );
+}
+            "##,
+            None,
+        );
+        assert_eq!(result, vec![]);
+    }
+
+    #[test]
+    fn missing_and_extra_columns() {
+        let result = check(
+            r##"
+struct Todo {
+    id: i64,
+    description: String,
+    done: bool,
+}
+
+async fn list_todos(pool: &PgPool) -> anyhow::Result<()> {
+    let recs = sqlx::query_as!(
+        Todo,
+        r#"
+SELECT id, title, COUNT(*)
+FROM todos
+        "#
+    );
+}
+            "##,
+            None,
+        );
+        let codes: Vec<&str> = result.iter().map(|d| d.code.as_str()).collect();
+        assert_eq!(
+            codes,
+            vec![
+                "query-as-extra-column",
+                "query-as-unnamed-column",
+                "query-as-missing-column",
+                "query-as-missing-column",
+            ]
+        );
+        // `title`
+        assert_eq!(
+            result[0].range,
+            Range {
+                start: Position {
+                    line: 11,
+                    character: 11,
+                },
+                end: Position {
+                    line: 11,
+                    character: 16,
+                },
+            }
+        );
+        assert_eq!(
+            result[2].message,
+            "field `description` of `Todo` is not selected by the query"
+        );
+        assert_eq!(
+            result[3].message,
+            "field `done` of `Todo` is not selected by the query"
+        );
+    }
+
+    #[test]
+    fn wildcard_expanded_with_catalog() {
+        let source_txt = r#"
+struct Todo {
+    id: i64,
+    description: String,
+}
+
+async fn list_todos(pool: &PgPool) {
+    let recs = sqlx::query_as!(Todo, "SELECT * FROM todos");
+}
+            "#;
+        // unknown columns: not checked
+        assert_eq!(check(source_txt, None), vec![]);
+
+        let catalog = Catalog::from_diesel_schema(
+            r#"
+diesel::table! {
+    todos (id) {
+        id -> Int8,
+        description -> Text,
+        done -> Bool,
+    }
+}
+            "#,
+        )
+        .unwrap();
+        let result = check(source_txt, Some(&catalog));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].message, "column `done` has no field in `Todo`");
+    }
+}
@@ -0,0 +1,527 @@
+use crate::literal::offset_of_location;
+use crate::syntax::parse_each_statement;
+use crate::{Range, SqlNode};
+use serde::Serialize;
+use sqlparser::ast::{
+    AlterTableOperation, AssignmentTarget, Expr, FromTable, Ident, ObjectName, ObjectType, Query,
+    Statement, TableFactor, Visit, Visitor,
+};
+use std::ops::ControlFlow;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReferenceKind {
+    Table,
+    Column,
+    Alias,
+    Cte,
+    Function,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Read,
+    Write,
+    Ddl,
+}
+
+/// An identifier of an embedded query.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    pub role: Role,
+    pub name: String,
+    /// the table of a column or an alias, when it can be resolved
+    pub table: Option<String>,
+    pub range: Range,
+    /// char offsets into the SQL, quotes excluded
+    #[serde(skip)]
+    pub start: usize,
+    #[serde(skip)]
+    pub end: usize,
+}
+
+/// Fill `references` of each node. Statements that cannot be parsed have none.
+pub fn link(sql_nodes: &mut [SqlNode]) {
+    for sql_node in sql_nodes.iter_mut() {
+        sql_node.references = resolve(sql_node);
+    }
+}
+
+/// References of every statement of a node, located in the Rust source.
+pub fn resolve(sql_node: &SqlNode) -> Vec<Reference> {
+    let sql = &sql_node.literal.value;
+    parse_each_statement(sql, sql_node.dialect)
+        .into_iter()
+        .filter_map(|parsed| parsed.statement.ok())
+        .flat_map(|statement| statement_references(sql, &statement))
+        .map(|r| Reference {
+            range: sql_node.literal.range_of(r.start, r.end),
+            ..r
+        })
+        .collect()
+}
+
+struct RawReference {
+    kind: ReferenceKind,
+    role: Role,
+    ident: Ident,
+    table: Option<String>,
+    // ex) `t` of `t.id`
+    qualifier: Option<String>,
+    // the innermost query, 0 for the statement itself
+    scope: usize,
+}
+
+#[derive(Default)]
+struct ReferenceVisitor {
+    role: Option<Role>,
+    // first idents of the written tables
+    write_targets: Vec<Ident>,
+    ctes: Vec<String>,
+    scopes: Vec<usize>,
+    scope_count: usize,
+    references: Vec<RawReference>,
+}
+
+impl ReferenceVisitor {
+    fn push(&mut self, kind: ReferenceKind, role: Role, ident: &Ident, table: Option<String>) {
+        self.references.push(RawReference {
+            kind,
+            role,
+            ident: ident.clone(),
+            table,
+            qualifier: None,
+            scope: self.scope(),
+        });
+    }
+
+    fn role(&self) -> Role {
+        self.role.unwrap_or(Role::Read)
+    }
+
+    fn scope(&self) -> usize {
+        self.scopes.last().copied().unwrap_or(0)
+    }
+}
+
+impl Visitor for ReferenceVisitor {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<()> {
+        // CTE definitions belong to the enclosing scope
+        for cte in query.with.iter().flat_map(|w| &w.cte_tables) {
+            self.ctes.push(cte.alias.name.value.clone());
+            self.push(ReferenceKind::Cte, Role::Read, &cte.alias.name, None);
+        }
+        self.scope_count += 1;
+        self.scopes.push(self.scope_count);
+        ControlFlow::Continue(())
+    }
+
+    fn post_visit_query(&mut self, _query: &Query) -> ControlFlow<()> {
+        self.scopes.pop();
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<()> {
+        let ident = match relation.0.last() {
+            Some(ident) => ident,
+            None => return ControlFlow::Continue(()),
+        };
+        let role = if self
+            .write_targets
+            .iter()
+            .any(|t| Some(t.span) == relation.0.first().map(|i| i.span))
+        {
+            Role::Write
+        } else {
+            self.role()
+        };
+        let kind = if self.ctes.contains(&ident.value) {
+            ReferenceKind::Cte
+        } else {
+            ReferenceKind::Table
+        };
+        self.push(kind, role, ident, None);
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_table_factor(&mut self, table_factor: &TableFactor) -> ControlFlow<()> {
+        match table_factor {
+            TableFactor::Table {
+                name,
+                alias: Some(alias),
+                ..
+            } => {
+                let table = name.0.last().map(|i| i.value.clone());
+                self.push(ReferenceKind::Alias, Role::Read, &alias.name, table);
+            }
+            TableFactor::Derived {
+                alias: Some(alias), ..
+            } => self.push(ReferenceKind::Alias, Role::Read, &alias.name, None),
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<()> {
+        match expr {
+            Expr::Identifier(ident) => self.push(ReferenceKind::Column, self.role(), ident, None),
+            Expr::CompoundIdentifier(idents) if idents.len() >= 2 => {
+                let qualifier = &idents[idents.len() - 2];
+                self.references.push(RawReference {
+                    kind: ReferenceKind::Table, // resolved later
+                    role: Role::Read,
+                    ident: qualifier.clone(),
+                    table: None,
+                    qualifier: Some(qualifier.value.clone()),
+                    scope: self.scope(),
+                });
+                self.references.push(RawReference {
+                    kind: ReferenceKind::Column,
+                    role: self.role(),
+                    ident: idents[idents.len() - 1].clone(),
+                    table: None,
+                    qualifier: Some(qualifier.value.clone()),
+                    scope: self.scope(),
+                });
+            }
+            Expr::Function(function) => {
+                if let Some(ident) = function.name.0.last() {
+                    self.push(ReferenceKind::Function, Role::Read, ident, None);
+                }
+            }
+            _ => {}
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+fn last_name(name: &ObjectName) -> Option<String> {
+    name.0.last().map(|i| i.value.clone())
+}
+
+fn statement_references(sql: &str, statement: &Statement) -> Vec<Reference> {
+    let mut visitor = ReferenceVisitor::default();
+    match statement {
+        Statement::Query(_) => {}
+        Statement::Insert(insert) => {
+            visitor
+                .write_targets
+                .extend(insert.table_name.0.first().cloned());
+            for column in &insert.columns {
+                visitor.push(
+                    ReferenceKind::Column,
+                    Role::Write,
+                    column,
+                    last_name(&insert.table_name),
+                );
+            }
+        }
+        Statement::Update {
+            table, assignments, ..
+        } => {
+            let table_name = match &table.relation {
+                TableFactor::Table { name, .. } => {
+                    visitor.write_targets.extend(name.0.first().cloned());
+                    last_name(name)
+                }
+                _ => None,
+            };
+            for assignment in assignments {
+                let columns = match &assignment.target {
+                    AssignmentTarget::ColumnName(name) => vec![name],
+                    AssignmentTarget::Tuple(names) => names.iter().collect(),
+                };
+                for ident in columns.into_iter().filter_map(|c| c.0.last()) {
+                    visitor.push(
+                        ReferenceKind::Column,
+                        Role::Write,
+                        ident,
+                        table_name.clone(),
+                    );
+                }
+            }
+        }
+        Statement::Delete(delete) => {
+            let from = match &delete.from {
+                FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from) => from,
+            };
+            // `DELETE t1 FROM t1 JOIN t2` deletes only from t1
+            let targets: Vec<&ObjectName> = if delete.tables.is_empty() {
+                from.iter()
+                    .filter_map(|t| match &t.relation {
+                        TableFactor::Table { name, .. } => Some(name),
+                        _ => None,
+                    })
+                    .collect()
+            } else {
+                delete.tables.iter().collect()
+            };
+            visitor
+                .write_targets
+                .extend(targets.iter().filter_map(|name| name.0.first().cloned()));
+        }
+        Statement::CreateTable(create_table) => {
+            visitor.role = Some(Role::Ddl);
+            for column in &create_table.columns {
+                visitor.push(
+                    ReferenceKind::Column,
+                    Role::Ddl,
+                    &column.name,
+                    last_name(&create_table.name),
+                );
+            }
+        }
+        Statement::AlterTable {
+            name, operations, ..
+        } => {
+            visitor.role = Some(Role::Ddl);
+            for operation in operations {
+                let columns = match operation {
+                    AlterTableOperation::AddColumn { column_def, .. } => vec![&column_def.name],
+                    AlterTableOperation::DropColumn { column_name, .. }
+                    | AlterTableOperation::AlterColumn { column_name, .. } => vec![column_name],
+                    AlterTableOperation::RenameColumn {
+                        old_column_name,
+                        new_column_name,
+                    } => vec![old_column_name, new_column_name],
+                    _ => vec![],
+                };
+                for column in columns {
+                    visitor.push(ReferenceKind::Column, Role::Ddl, column, last_name(name));
+                }
+            }
+        }
+        Statement::Drop {
+            object_type: ObjectType::Table,
+            names,
+            ..
+        } => {
+            for ident in names.iter().filter_map(|n| n.0.last()) {
+                visitor.push(ReferenceKind::Table, Role::Ddl, ident, None);
+            }
+        }
+        _ => visitor.role = Some(Role::Ddl),
+    }
+    let _ = statement.visit(&mut visitor);
+
+    resolve_tables(&mut visitor);
+    let mut references: Vec<Reference> = visitor
+        .references
+        .into_iter()
+        .map(|r| {
+            let mut start =
+                offset_of_location(sql, r.ident.span.start.line, r.ident.span.start.column);
+            let mut end = offset_of_location(sql, r.ident.span.end.line, r.ident.span.end.column);
+            if r.ident.quote_style.is_some() {
+                start += 1;
+                end -= 1;
+            }
+            Reference {
+                kind: r.kind,
+                role: r.role,
+                name: r.ident.value,
+                table: r.table,
+                range: Range::default(),
+                start,
+                end,
+            }
+        })
+        .collect();
+    references.sort_by_key(|r| r.start);
+    references
+}
+
+// resolve qualifiers and the tables of columns within a statement
+fn resolve_tables(visitor: &mut ReferenceVisitor) {
+    let aliases: Vec<(String, Option<String>)> = visitor
+        .references
+        .iter()
+        .filter(|r| r.kind == ReferenceKind::Alias)
+        .map(|r| (r.ident.value.clone(), r.table.clone()))
+        .collect();
+    // (scope, relation), a CTE has no table
+    let relations: Vec<(usize, Option<String>)> = visitor
+        .references
+        .iter()
+        .filter(|r| matches!(r.kind, ReferenceKind::Table | ReferenceKind::Cte))
+        .filter(|r| r.qualifier.is_none())
+        .map(|r| {
+            let table = (r.kind == ReferenceKind::Table).then(|| r.ident.value.clone());
+            (r.scope, table)
+        })
+        .collect();
+    let ctes = visitor.ctes.clone();
+
+    for reference in visitor.references.iter_mut() {
+        match (&reference.kind, reference.qualifier.as_deref()) {
+            (ReferenceKind::Table, Some(qualifier)) => {
+                if let Some((_, table)) = aliases.iter().find(|(a, _)| a == qualifier) {
+                    reference.kind = ReferenceKind::Alias;
+                    reference.table = table.clone();
+                } else if ctes.iter().any(|c| c == qualifier) {
+                    reference.kind = ReferenceKind::Cte;
+                }
+            }
+            (ReferenceKind::Column, Some(qualifier)) => {
+                reference.table = match aliases.iter().find(|(a, _)| a == qualifier) {
+                    Some((_, table)) => table.clone(),
+                    None => Some(qualifier.to_string()),
+                };
+            }
+            (ReferenceKind::Column, None) if reference.table.is_none() => {
+                let mut scope_relations = relations.iter().filter(|(s, _)| *s == reference.scope);
+                if let (Some((_, table)), None) = (scope_relations.next(), scope_relations.next()) {
+                    reference.table = table.clone();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::{default_configs, extract_sql_nodes, Position};
+    use pretty_assertions::assert_eq;
+
+    // (kind, role, name, table)
+    type Summary = (ReferenceKind, Role, String, Option<String>);
+
+    fn references(source_txt: &str) -> Vec<Vec<Summary>> {
+        let mut sql_nodes =
+            extract_sql_nodes(source_txt, &default_configs(), Dialect::PostgreSql).unwrap();
+        link(&mut sql_nodes);
+        sql_nodes
+            .iter()
+            .map(|n| {
+                n.references
+                    .iter()
+                    .map(|r| (r.kind, r.role, r.name.clone(), r.table.clone()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn r(kind: ReferenceKind, role: Role, name: &str, table: Option<&str>) -> Summary {
+        (kind, role, name.to_string(), table.map(str::to_string))
+    }
+
+    #[test]
+    fn read_and_write_references() {
+        use ReferenceKind::*;
+        use Role::*;
+        let result = references(
+            r##"
+async fn update(pool: &PgPool) {
+    sqlx::query!("SELECT t.id, lower(description) FROM todos t WHERE done");
+    sqlx::query!("UPDATE todos SET done = TRUE WHERE id = $1", id);
+    sqlx::query!("INSERT INTO todos (description) SELECT title FROM drafts");
+}
+            "##,
+        );
+        assert_eq!(
+            result,
+            vec![
+                vec![
+                    r(Alias, Read, "t", Some("todos")),
+                    r(Column, Read, "id", Some("todos")),
+                    r(Function, Read, "lower", None),
+                    r(Column, Read, "description", Some("todos")),
+                    r(Table, Read, "todos", None),
+                    r(Alias, Read, "t", Some("todos")),
+                    r(Column, Read, "done", Some("todos")),
+                ],
+                vec![
+                    r(Table, Write, "todos", None),
+                    r(Column, Write, "done", Some("todos")),
+                    r(Column, Read, "id", Some("todos")),
+                ],
+                vec![
+                    r(Table, Write, "todos", None),
+                    r(Column, Write, "description", Some("todos")),
+                    r(Column, Read, "title", Some("drafts")),
+                    r(Table, Read, "drafts", None),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn cte_and_ddl_references() {
+        use ReferenceKind::*;
+        use Role::*;
+        let result = references(
+            r##"
+fn main() {
+    sqlx::query!("WITH open AS (SELECT id FROM todos) SELECT id FROM open");
+    sqlx::query!("ALTER TABLE todos ADD COLUMN title TEXT");
+}
+            "##,
+        );
+        assert_eq!(
+            result,
+            vec![
+                vec![
+                    r(Cte, Read, "open", None),
+                    r(Column, Read, "id", Some("todos")),
+                    r(Table, Read, "todos", None),
+                    r(Column, Read, "id", None),
+                    r(Cte, Read, "open", None),
+                ],
+                vec![
+                    r(Table, Ddl, "todos", None),
+                    r(Column, Ddl, "title", Some("todos")),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_through_escapes() {
+        let mut sql_nodes = extract_sql_nodes(
+            r#"
+fn main() {
+    sqlx::query!("SELECT \"id\" FROM todos");
+}
+            "#,
+            &default_configs(),
+            Dialect::PostgreSql,
+        )
+        .unwrap();
+        link(&mut sql_nodes);
+        let references = &sql_nodes[0].references;
+        // `id` without the escaped quotes
+        assert_eq!(
+            references[0].range,
+            Range {
+                start: Position {
+                    line: 2,
+                    character: 27,
+                },
+                end: Position {
+                    line: 2,
+                    character: 29,
+                },
+            }
+        );
+        assert_eq!(
+            references[1].range,
+            Range {
+                start: Position {
+                    line: 2,
+                    character: 37,
+                },
+                end: Position {
+                    line: 2,
+                    character: 42,
+                },
+            }
+        );
+    }
+}
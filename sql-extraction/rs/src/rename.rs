@@ -0,0 +1,270 @@
+use crate::dialect::Dialect;
+use crate::edit::{FileEdit, TextEdit};
+use crate::format::{encode, raw_hashes};
+use crate::literal::LiteralKind;
+use crate::references::{self, Reference, ReferenceKind};
+use crate::schema::same_name;
+use crate::workspace::{read_rust_files, SourceFile};
+use crate::{extract_sql_nodes, Config, SqlNode};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A table, or a column of a table when `column` is set.
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct RenameTarget {
+    pub table: String,
+    pub column: Option<String>,
+}
+
+/// Edits renaming `target` to `new_name` in the query of a node.
+/// Columns whose table cannot be resolved are left alone. A raw string the new name would
+/// end early, ex) `"Order"` in `r"..."`, is rewritten as a whole with more `#`.
+pub fn rename_edits(sql_node: &SqlNode, target: &RenameTarget, new_name: &str) -> Vec<TextEdit> {
+    let mut references: Vec<Reference> = references::resolve(sql_node)
+        .into_iter()
+        .filter(|r| match &target.column {
+            None => r.kind == ReferenceKind::Table && same_name(&r.name, &target.table),
+            Some(column) => {
+                r.kind == ReferenceKind::Column
+                    && same_name(&r.name, column)
+                    && r.table
+                        .as_deref()
+                        .is_some_and(|t| same_name(t, &target.table))
+            }
+        })
+        .collect();
+    references.sort_by_key(|r| r.start);
+    references.dedup_by_key(|r| (r.start, r.end));
+
+    let literal = &sql_node.literal;
+    if let LiteralKind::RawStr { hashes } = literal.kind {
+        let value = renamed(&literal.value, &references, new_name);
+        if !references.is_empty() && raw_hashes(&value) > hashes {
+            return vec![TextEdit {
+                range: literal.range,
+                new_text: encode(literal.kind, &value),
+            }];
+        }
+    }
+    references
+        .iter()
+        .map(|r| TextEdit {
+            range: r.range,
+            new_text: literal.kind.escape(new_name),
+        })
+        .collect()
+}
+
+// `value` with `new_name` in place of the sorted `references`
+fn renamed(value: &str, references: &[Reference], new_name: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut renamed = String::new();
+    let mut i = 0;
+    for reference in references {
+        renamed.extend(&chars[i..reference.start]);
+        renamed.push_str(new_name);
+        i = reference.end;
+    }
+    renamed.extend(&chars[i..]);
+    renamed
+}
+
+/// Edits of every file that has a query referencing `target`.
+/// Files that cannot be parsed are skipped.
+pub fn rename_in_sources(
+    sources: &[SourceFile],
+    configs: &[Config],
+    dialect: Dialect,
+    target: &RenameTarget,
+    new_name: &str,
+) -> Vec<FileEdit> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let sql_nodes = extract_sql_nodes(&source.content, configs, dialect).ok()?;
+            let edits: Vec<TextEdit> = sql_nodes
+                .iter()
+                .flat_map(|sql_node| rename_edits(sql_node, target, new_name))
+                .collect();
+            (!edits.is_empty()).then(|| FileEdit {
+                path: source.path.clone(),
+                edits,
+            })
+        })
+        .collect()
+}
+
+/// `rename_in_sources` for the Rust files under `root`.
+pub fn rename_in_workspace(
+    root: &Path,
+    configs: &[Config],
+    dialect: Dialect,
+    target: &RenameTarget,
+    new_name: &str,
+) -> std::io::Result<Vec<FileEdit>> {
+    Ok(rename_in_sources(
+        &read_rust_files(root)?,
+        configs,
+        dialect,
+        target,
+        new_name,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{default_configs, Position, Range};
+    use pretty_assertions::assert_eq;
+
+    fn source(path: &str, content: &str) -> SourceFile {
+        SourceFile {
+            path: path.to_string(),
+            content: content.to_string(),
+        }
+    }
+
+    fn sql_query_configs() -> Vec<Config> {
+        let mut configs = default_configs();
+        configs.push(Config {
+            functionName: "sql_query".to_string(),
+            sqlArgNo: 0,
+            isMacro: false,
+            dialect: None,
+        });
+        configs
+    }
+
+    #[test]
+    fn rename_column_of_one_table_only() {
+        let sources = vec![
+            source(
+                "src/main.rs",
+                r##"
+async fn list(pool: &PgPool) {
+    sqlx::query!("SELECT description FROM todos");
+    sqlx::query!("SELECT description FROM tags");
+    sqlx::query!(r#"UPDATE todos SET description = $1"#, description);
+}
+"##,
+            ),
+            source("src/lib.rs", "fn main() {}"),
+        ];
+        let target = RenameTarget {
+            table: "todos".to_string(),
+            column: Some("description".to_string()),
+        };
+        let result = rename_in_sources(
+            &sources,
+            &default_configs(),
+            Dialect::PostgreSql,
+            &target,
+            "\"Title\"",
+        );
+        assert_eq!(
+            result,
+            vec![FileEdit {
+                path: "src/main.rs".to_string(),
+                edits: vec![
+                    TextEdit {
+                        range: Range {
+                            start: Position {
+                                line: 2,
+                                character: 25,
+                            },
+                            end: Position {
+                                line: 2,
+                                character: 36,
+                            },
+                        },
+                        // re-escaped in a normal string
+                        new_text: "\\\"Title\\\"".to_string(),
+                    },
+                    TextEdit {
+                        range: Range {
+                            start: Position {
+                                line: 4,
+                                character: 37,
+                            },
+                            end: Position {
+                                line: 4,
+                                character: 48,
+                            },
+                        },
+                        new_text: "\"Title\"".to_string(),
+                    },
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn rename_to_quoted_name_in_raw_string() {
+        let sources = vec![source(
+            "src/main.rs",
+            r#"
+async fn list(pool: &PgPool) {
+    sqlx::query!(r"SELECT id FROM orders WHERE orders.id = 1");
+}
+"#,
+        )];
+        let target = RenameTarget {
+            table: "orders".to_string(),
+            column: None,
+        };
+        let result = rename_in_sources(
+            &sources,
+            &default_configs(),
+            Dialect::PostgreSql,
+            &target,
+            "\"Order\"",
+        );
+        // `"` would end `r"..."`
+        assert_eq!(
+            result[0].edits,
+            vec![TextEdit {
+                range: Range {
+                    start: Position {
+                        line: 2,
+                        character: 17,
+                    },
+                    end: Position {
+                        line: 2,
+                        character: 61,
+                    },
+                },
+                new_text: r##"r#"SELECT id FROM "Order" WHERE "Order".id = 1"#"##.to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn rename_table_in_diesel_sql_query() {
+        let sources = vec![source(
+            "src/diesel.rs",
+            r#"
+fn list(conn: &mut PgConnection) {
+    diesel::sql_query("SELECT t.id FROM todos t JOIN todo_tags ON todos.id = todo_tags.todo_id");
+}
+"#,
+        )];
+        let target = RenameTarget {
+            table: "todos".to_string(),
+            column: None,
+        };
+        let result = rename_in_sources(
+            &sources,
+            &sql_query_configs(),
+            Dialect::PostgreSql,
+            &target,
+            "tasks",
+        );
+        let ranges: Vec<(usize, usize)> = result[0]
+            .edits
+            .iter()
+            .map(|e| (e.range.start.character, e.range.end.character))
+            .collect();
+        // `todos` of FROM and of `todos.id`, not the alias nor `todo_tags`
+        assert_eq!(ranges, vec![(40, 45), (66, 71)]);
+    }
+}
@@ -0,0 +1,173 @@
+use super::Catalog;
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::literal::offset_of_location;
+use crate::SqlNode;
+use sqlparser::ast::{Ident, ObjectName, Query, Statement, Visit, Visitor};
+use sqlparser::parser::Parser;
+use std::ops::ControlFlow;
+
+/// Report tables used by a query that are not in the catalog.
+pub fn check_schema(sql_node: &SqlNode, catalog: &Catalog) -> Vec<Diagnostic> {
+    let parser_dialect = sql_node.dialect.parser_dialect();
+    // syntax errors are reported by `check_syntax`
+    let statements = match Parser::parse_sql(&*parser_dialect, &sql_node.literal.value) {
+        Ok(statements) => statements,
+        Err(_) => return vec![],
+    };
+
+    let mut visitor = RelationVisitor::default();
+    for statement in &statements {
+        // only queries run against existing tables, DDL may create them
+        if matches!(
+            statement,
+            Statement::Query(_)
+                | Statement::Insert(_)
+                | Statement::Update { .. }
+                | Statement::Delete(_)
+        ) {
+            let _ = statement.visit(&mut visitor);
+        }
+    }
+
+    visitor
+        .relations
+        .iter()
+        .filter_map(|name| {
+            let ident = name.0.last()?;
+            let is_cte = name.0.len() == 1
+                && visitor
+                    .cte_names
+                    .iter()
+                    .any(|cte| cte.eq_ignore_ascii_case(&ident.value));
+            if is_cte || catalog.table(&ident.value).is_some() {
+                return None;
+            }
+            Some(Diagnostic {
+                range: ident_range(sql_node, ident),
+                severity: Severity::Warning,
+                code: "unknown-table".to_string(),
+                message: format!("table \"{}\" does not exist in the schema", ident.value),
+            })
+        })
+        .collect()
+}
+
+fn ident_range(sql_node: &SqlNode, ident: &Ident) -> crate::Range {
+    let sql = &sql_node.literal.value;
+    sql_node.literal.range_of(
+        offset_of_location(sql, ident.span.start.line, ident.span.start.column),
+        offset_of_location(sql, ident.span.end.line, ident.span.end.column),
+    )
+}
+
+#[derive(Default)]
+struct RelationVisitor {
+    relations: Vec<ObjectName>,
+    cte_names: Vec<String>,
+}
+
+impl Visitor for RelationVisitor {
+    type Break = ();
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<Self::Break> {
+        if let Some(with) = &query.with {
+            self.cte_names.extend(
+                with.cte_tables
+                    .iter()
+                    .map(|cte| cte.alias.name.value.clone()),
+            );
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_relation(&mut self, relation: &ObjectName) -> ControlFlow<Self::Break> {
+        self.relations.push(relation.clone());
+        ControlFlow::Continue(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dialect::Dialect;
+    use crate::schema::migrations::Migration;
+    use crate::{extract_sql_nodes, Config, Position, Range};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unknown_table_in_diesel_sql_query() {
+        let catalog = Catalog::from_diesel_schema(
+            r#"
+diesel::table! {
+    todos (id) {
+        id -> Int4,
+        description -> Text,
+    }
+}
+            "#,
+        )
+        .unwrap();
+        let sql_nodes = extract_sql_nodes(
+            r##"
+fn main() {
+    let results = diesel::sql_query(
+        r#"
+WITH recent AS (SELECT id FROM todos)
+SELECT t.id FROM recent JOIN todo t ON t.id = recent.id
+        "#,
+    );
+}
+            "##,
+            &[Config {
+                functionName: "sql_query".to_string(),
+                sqlArgNo: 0,
+                isMacro: false,
+                dialect: None,
+            }],
+            Dialect::PostgreSql,
+        )
+        .unwrap();
+        let result = check_schema(&sql_nodes[0], &catalog);
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].range,
+            Range {
+                start: Position {
+                    line: 5,
+                    character: 29,
+                },
+                end: Position {
+                    line: 5,
+                    character: 33,
+                },
+            }
+        );
+        assert_eq!(result[0].code, "unknown-table");
+    }
+
+    #[test]
+    fn known_tables_from_migrations() {
+        let (catalog, _) = Catalog::from_migrations(
+            &[Migration {
+                path: "migrations/1_todos.sql".to_string(),
+                sql: "CREATE TABLE todos (id BIGSERIAL PRIMARY KEY, done BOOLEAN)".to_string(),
+            }],
+            Dialect::PostgreSql,
+        );
+        let sql_nodes = extract_sql_nodes(
+            r#"
+async fn complete_todo(pool: &PgPool, id: i64) {
+    sqlx::query!("UPDATE todos SET done = TRUE WHERE id = $1", id);
+    sqlx::query!("DELETE FROM public.todos WHERE id = $1", id);
+    sqlx::query!("CREATE TABLE users (id BIGINT)");
+}
+            "#,
+            &crate::default_configs(),
+            Dialect::Generic,
+        )
+        .unwrap();
+        for sql_node in &sql_nodes {
+            assert_eq!(check_schema(sql_node, &catalog), vec![]);
+        }
+    }
+}
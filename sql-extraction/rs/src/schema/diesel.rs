@@ -0,0 +1,415 @@
+use super::{Catalog, Column, ForeignKey, Table};
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use std::path::Path;
+use syn::visit::{self, Visit};
+
+/// Read a diesel `schema.rs` into a catalog.
+pub fn load_catalog(path: &Path) -> std::io::Result<Result<Catalog, syn::Error>> {
+    Ok(Catalog::from_diesel_schema(&std::fs::read_to_string(path)?))
+}
+
+impl Catalog {
+    /// Build a catalog from the `table!` and `joinable!` macros of a diesel `schema.rs`.
+    pub fn from_diesel_schema(source_txt: &str) -> Result<Catalog, syn::Error> {
+        let ast = syn::parse_file(source_txt)?;
+        let mut visitor = SchemaVisitor::default();
+        visitor.visit_file(&ast);
+
+        let mut catalog = Catalog {
+            tables: visitor.tables,
+        };
+        // joinable!(child -> parent (foreign_key)) refers to the primary key of the parent
+        for (child, parent, column) in visitor.joinables {
+            let (foreign_table, referred_columns) = match catalog.table(&parent) {
+                Some(table) => (table.name.clone(), table.primary_key.clone()),
+                None => continue,
+            };
+            if let Some(table) = catalog.table_mut(&child) {
+                table.foreign_keys.push(ForeignKey {
+                    columns: vec![column],
+                    foreign_table,
+                    referred_columns,
+                });
+            }
+        }
+        Ok(catalog)
+    }
+}
+
+#[derive(Default)]
+struct SchemaVisitor {
+    tables: Vec<Table>,
+    joinables: Vec<(String, String, String)>, // (child, parent, foreign key column)
+}
+
+impl<'ast> Visit<'ast> for SchemaVisitor {
+    // diesel::table! { ... }, diesel::joinable!(...)
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        let name = mac.path.segments.last().map(|s| s.ident.to_string());
+        match name.as_deref() {
+            Some("table") => self.tables.extend(parse_tables(mac.tokens.clone())),
+            Some("joinable") => self.joinables.extend(parse_joinable(mac.tokens.clone())),
+            _ => {}
+        }
+        visit::visit_macro(self, mac);
+    }
+}
+
+/// Parse the body of `table!`, ex)
+/// ```text
+/// #[sql_name = "todo_items"]
+/// todos (id) {
+///     id -> Int4,
+///     description -> Nullable<Text>,
+/// }
+/// ```
+fn parse_tables(tokens: TokenStream) -> Vec<Table> {
+    let mut tables = Vec::new();
+    let mut sql_name: Option<String> = None;
+    let mut name: Option<String> = None;
+    let mut primary_key: Vec<String> = vec![];
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            // `use diesel::sql_types::*;`
+            TokenTree::Ident(ident) if ident == "use" => {
+                for token in tokens.by_ref() {
+                    if matches!(&token, TokenTree::Punct(p) if p.as_char() == ';') {
+                        break;
+                    }
+                }
+            }
+            // `#[sql_name = "..."]`, doc comments
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.next() {
+                    if let Some(value) = sql_name_attr(group.stream()) {
+                        sql_name = Some(value);
+                    }
+                }
+            }
+            // `public.todos`: keep the last segment
+            TokenTree::Ident(ident) => name = Some(ident.to_string()),
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+                primary_key = idents(group.stream());
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                let table_name = match (sql_name.take(), name.take()) {
+                    (Some(sql_name), _) => sql_name,
+                    (None, Some(name)) => name,
+                    (None, None) => continue,
+                };
+                // diesel defaults the primary key to `id`
+                let primary_key = match std::mem::take(&mut primary_key) {
+                    pk if pk.is_empty() => vec!["id".to_string()],
+                    pk => pk,
+                };
+                let columns = parse_columns(group.stream());
+                // `#[sql_name]` of columns is used in SQL, primary keys are written with Rust names
+                let primary_key = primary_key
+                    .into_iter()
+                    .map(|pk| {
+                        columns
+                            .iter()
+                            .find(|(rust_name, _)| *rust_name == pk)
+                            .map(|(_, c)| c.name.clone())
+                            .unwrap_or(pk)
+                    })
+                    .collect();
+                tables.push(Table {
+                    name: table_name,
+                    columns: columns.into_iter().map(|(_, c)| c).collect(),
+                    primary_key,
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+    }
+    tables
+}
+
+/// Parse `name -> Type,` pairs, returning the Rust name with each column.
+fn parse_columns(tokens: TokenStream) -> Vec<(String, Column)> {
+    let mut columns = Vec::new();
+    let mut tokens = tokens.into_iter().peekable();
+    let mut sql_name: Option<String> = None;
+    while let Some(token) = tokens.next() {
+        let rust_name = match token {
+            TokenTree::Punct(p) if p.as_char() == '#' => {
+                if let Some(TokenTree::Group(group)) = tokens.next() {
+                    if let Some(value) = sql_name_attr(group.stream()) {
+                        sql_name = Some(value);
+                    }
+                }
+                continue;
+            }
+            // raw identifiers, ex) r#type
+            TokenTree::Ident(ident) => ident.to_string().trim_start_matches("r#").to_string(),
+            _ => continue,
+        };
+
+        // `->`
+        let arrow: String = (0..2)
+            .filter_map(|_| match tokens.next() {
+                Some(TokenTree::Punct(p)) => Some(p.as_char()),
+                _ => None,
+            })
+            .collect();
+        if arrow != "->" {
+            continue;
+        }
+
+        // type until the next top-level `,`
+        let mut diesel_type = Vec::new();
+        let mut depth = 0;
+        while let Some(token) = tokens.peek() {
+            if let TokenTree::Punct(p) = token {
+                match p.as_char() {
+                    '<' => depth += 1,
+                    '>' => depth -= 1,
+                    ',' if depth == 0 => {
+                        tokens.next();
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            diesel_type.push(tokens.next().unwrap());
+        }
+
+        let (data_type, nullable) = sql_type(&diesel_type);
+        columns.push((
+            rust_name.clone(),
+            Column {
+                name: sql_name.take().unwrap_or(rust_name),
+                data_type,
+                nullable,
+                default: None,
+            },
+        ));
+    }
+    columns
+}
+
+/// Map a diesel SQL type to a column type, ex) `Nullable<Int4>` -> ("INTEGER", true)
+fn sql_type(tokens: &[TokenTree]) -> (String, bool) {
+    let idents: Vec<String> = tokens
+        .iter()
+        .filter_map(|t| match t {
+            TokenTree::Ident(ident) => Some(ident.to_string()),
+            _ => None,
+        })
+        // `diesel::sql_types::Int4`, `sql_types::Nullable<...>`
+        .filter(|ident| ident != "diesel" && ident != "sql_types")
+        .collect();
+
+    let nullable = idents.first().is_some_and(|i| i == "Nullable");
+    let idents = if nullable { &idents[1..] } else { &idents[..] };
+    let data_type = match idents {
+        [array, inner] if array == "Array" => format!("{}[]", diesel_type_name(inner)),
+        [single] => diesel_type_name(single),
+        _ => idents.join(" "),
+    };
+    (data_type, nullable)
+}
+
+// https://docs.rs/diesel/latest/diesel/sql_types/index.html
+fn diesel_type_name(diesel_type: &str) -> String {
+    match diesel_type {
+        "SmallInt" | "Int2" => "SMALLINT",
+        "Integer" | "Int4" => "INTEGER",
+        "BigInt" | "Int8" => "BIGINT",
+        "Float" | "Float4" => "REAL",
+        "Double" | "Float8" => "DOUBLE PRECISION",
+        "Numeric" | "Decimal" => "NUMERIC",
+        "Text" => "TEXT",
+        "VarChar" | "Varchar" => "VARCHAR",
+        "Bool" => "BOOLEAN",
+        "Date" => "DATE",
+        "Time" => "TIME",
+        "Timestamp" => "TIMESTAMP",
+        "Timestamptz" => "TIMESTAMPTZ",
+        "Interval" => "INTERVAL",
+        "Uuid" => "UUID",
+        "Json" => "JSON",
+        "Jsonb" => "JSONB",
+        "Binary" | "Bytea" | "Blob" => "BYTEA",
+        "Money" => "MONEY",
+        "Inet" => "INET",
+        "Cidr" => "CIDR",
+        "MacAddr" => "MACADDR",
+        other => other,
+    }
+    .to_string()
+}
+
+// joinable!(posts -> users (user_id));
+fn parse_joinable(tokens: TokenStream) -> Option<(String, String, String)> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    match tokens.as_slice() {
+        [TokenTree::Ident(child), TokenTree::Punct(_), TokenTree::Punct(_), TokenTree::Ident(parent), TokenTree::Group(fk)] => {
+            Some((
+                child.to_string(),
+                parent.to_string(),
+                idents(fk.stream()).first()?.clone(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+// [sql_name = "todo_items"] -> "todo_items"
+fn sql_name_attr(tokens: TokenStream) -> Option<String> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    match tokens.as_slice() {
+        [TokenTree::Ident(name), TokenTree::Punct(_), TokenTree::Literal(value)]
+            if name == "sql_name" =>
+        {
+            Some(value.to_string().trim_matches('"').to_string())
+        }
+        _ => None,
+    }
+}
+
+fn idents(tokens: TokenStream) -> Vec<String> {
+    tokens
+        .into_iter()
+        .filter_map(|t| match t {
+            TokenTree::Ident(ident) => Some(ident.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn read_table_macros() {
+        let catalog = Catalog::from_diesel_schema(
+            r#"
+// @generated automatically by Diesel CLI.
+
+diesel::table! {
+    use diesel::sql_types::*;
+
+    todos (id) {
+        id -> Int8,
+        description -> Text,
+        done -> Bool,
+        tags -> Nullable<Array<diesel::sql_types::Text>>,
+        #[sql_name = "type"]
+        type_ -> Varchar,
+    }
+}
+
+diesel::table! {
+    /// Representation of the `users` table.
+    #[sql_name = "app_users"]
+    users {
+        id -> Int4,
+        name -> Nullable<Text>,
+    }
+}
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            catalog,
+            Catalog {
+                tables: vec![
+                    Table {
+                        name: "todos".to_string(),
+                        columns: vec![
+                            Column {
+                                name: "id".to_string(),
+                                data_type: "BIGINT".to_string(),
+                                nullable: false,
+                                default: None,
+                            },
+                            Column {
+                                name: "description".to_string(),
+                                data_type: "TEXT".to_string(),
+                                nullable: false,
+                                default: None,
+                            },
+                            Column {
+                                name: "done".to_string(),
+                                data_type: "BOOLEAN".to_string(),
+                                nullable: false,
+                                default: None,
+                            },
+                            Column {
+                                name: "tags".to_string(),
+                                data_type: "TEXT[]".to_string(),
+                                nullable: true,
+                                default: None,
+                            },
+                            Column {
+                                name: "type".to_string(),
+                                data_type: "VARCHAR".to_string(),
+                                nullable: false,
+                                default: None,
+                            },
+                        ],
+                        primary_key: vec!["id".to_string()],
+                        ..Default::default()
+                    },
+                    Table {
+                        name: "app_users".to_string(),
+                        columns: vec![
+                            Column {
+                                name: "id".to_string(),
+                                data_type: "INTEGER".to_string(),
+                                nullable: false,
+                                default: None,
+                            },
+                            Column {
+                                name: "name".to_string(),
+                                data_type: "TEXT".to_string(),
+                                nullable: true,
+                                default: None,
+                            },
+                        ],
+                        primary_key: vec!["id".to_string()],
+                        ..Default::default()
+                    },
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn joinable_to_foreign_key() {
+        let catalog = Catalog::from_diesel_schema(
+            r#"
+table! {
+    users (user_id) {
+        user_id -> Int4,
+    }
+}
+
+table! {
+    posts (id) {
+        id -> Int4,
+        author_id -> Int4,
+    }
+}
+
+joinable!(posts -> users (author_id));
+allow_tables_to_appear_in_same_query!(posts, users);
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            catalog.table("posts").unwrap().foreign_keys,
+            vec![ForeignKey {
+                columns: vec!["author_id".to_string()],
+                foreign_table: "users".to_string(),
+                referred_columns: vec!["user_id".to_string()],
+            }]
+        );
+    }
+}
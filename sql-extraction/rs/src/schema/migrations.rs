@@ -0,0 +1,626 @@
+use super::{same_name, Catalog, Column, ForeignKey, Index, SchemaError, Table};
+use crate::dialect::Dialect;
+use crate::syntax::parse_each_statement;
+use serde::Deserialize;
+use sqlparser::ast::{
+    AlterColumnOperation, AlterTableOperation, ColumnDef, ColumnOption, CreateIndex, CreateTable,
+    Expr, ObjectName, ObjectType, Statement, TableConstraint,
+};
+use std::path::Path;
+
+/// A sqlx migration file, ex) `migrations/20200718111257_todos.sql`
+#[derive(Deserialize, Debug, Clone)]
+pub struct Migration {
+    pub path: String,
+    pub sql: String,
+}
+
+/// Version of a sqlx migration file name, `None` for files sqlx does not run.
+/// ex) "20200718111257_todos.sql" -> 20200718111257, "1_init.down.sql" -> None
+fn migration_version(file_name: &str) -> Option<i64> {
+    if !file_name.ends_with(".sql") || file_name.ends_with(".down.sql") {
+        return None;
+    }
+    let (version, _) = file_name.split_once('_')?;
+    version.parse().ok()
+}
+
+/// Read the migrations of a directory in the order sqlx applies them.
+pub fn read_migrations(dir: &Path) -> std::io::Result<Vec<Migration>> {
+    let mut versioned = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if let Some(version) = migration_version(&file_name) {
+            versioned.push((version, path));
+        }
+    }
+    versioned.sort();
+
+    versioned
+        .into_iter()
+        .map(|(_, path)| {
+            Ok(Migration {
+                sql: std::fs::read_to_string(&path)?,
+                path: path.to_string_lossy().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Read a migrations directory and replay it into a catalog.
+pub fn load_catalog(dir: &Path, dialect: Dialect) -> std::io::Result<(Catalog, Vec<SchemaError>)> {
+    Ok(Catalog::from_migrations(&read_migrations(dir)?, dialect))
+}
+
+impl Catalog {
+    /// Replay migrations in order. Statements that cannot be parsed are reported and skipped.
+    pub fn from_migrations(
+        migrations: &[Migration],
+        dialect: Dialect,
+    ) -> (Catalog, Vec<SchemaError>) {
+        let mut catalog = Catalog::default();
+        let mut errors = Vec::new();
+        for migration in migrations {
+            for parsed in parse_each_statement(&migration.sql, dialect) {
+                match parsed.statement {
+                    Ok(statement) => catalog.apply(&statement),
+                    Err(message) => errors.push(SchemaError {
+                        path: migration.path.clone(),
+                        message,
+                    }),
+                }
+            }
+        }
+        (catalog, errors)
+    }
+
+    /// Apply a DDL statement. Other statements are ignored.
+    pub fn apply(&mut self, statement: &Statement) {
+        match statement {
+            Statement::CreateTable(create_table) => self.create_table(create_table),
+            Statement::AlterTable {
+                name, operations, ..
+            } => {
+                for operation in operations {
+                    self.alter_table(&object_name(name), operation);
+                }
+            }
+            Statement::CreateIndex(create_index) => self.create_index(create_index),
+            Statement::Drop {
+                object_type: ObjectType::Table,
+                names,
+                ..
+            } => {
+                for name in names {
+                    self.drop_table(&object_name(name));
+                }
+            }
+            Statement::Drop {
+                object_type: ObjectType::Index,
+                names,
+                ..
+            } => {
+                for name in names {
+                    let name = object_name(name);
+                    for table in &mut self.tables {
+                        table
+                            .indexes
+                            .retain(|i| i.name.as_deref() != Some(name.as_str()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn create_table(&mut self, create_table: &CreateTable) {
+        let name = object_name(&create_table.name);
+        if self.table(&name).is_some() {
+            // CREATE TABLE IF NOT EXISTS, or a failing migration: keep the first definition
+            return;
+        }
+        let mut table = Table {
+            name,
+            ..Default::default()
+        };
+        for column_def in &create_table.columns {
+            table.add_column(column_def);
+        }
+        for constraint in &create_table.constraints {
+            table.add_constraint(constraint);
+        }
+        self.tables.push(table);
+    }
+
+    fn alter_table(&mut self, table_name: &str, operation: &AlterTableOperation) {
+        match operation {
+            AlterTableOperation::RenameTable { table_name: to } => {
+                let to = object_name(to);
+                if let Some(table) = self.table_mut(table_name) {
+                    table.name = to.clone();
+                }
+                for foreign_key in self
+                    .tables
+                    .iter_mut()
+                    .flat_map(|t| t.foreign_keys.iter_mut())
+                {
+                    if foreign_key.foreign_table.eq_ignore_ascii_case(table_name) {
+                        foreign_key.foreign_table = to.clone();
+                    }
+                }
+                return;
+            }
+            AlterTableOperation::RenameColumn {
+                old_column_name,
+                new_column_name,
+            } => {
+                self.rename_column(table_name, &old_column_name.value, &new_column_name.value);
+                return;
+            }
+            // MySQL: CHANGE old new TYPE ...
+            AlterTableOperation::ChangeColumn {
+                old_name,
+                new_name,
+                data_type,
+                options,
+                ..
+            } => {
+                self.rename_column(table_name, &old_name.value, &new_name.value);
+                if let Some(table) = self.table_mut(table_name) {
+                    // primary key columns stay NOT NULL
+                    let primary_key = table
+                        .primary_key
+                        .iter()
+                        .any(|c| same_name(c, &new_name.value));
+                    if let Some(c) = table.column_mut(&new_name.value) {
+                        *c = column(&new_name.value, data_type, options);
+                        c.nullable &= !primary_key;
+                    }
+                }
+                return;
+            }
+            _ => {}
+        }
+        let table = match self.table_mut(table_name) {
+            Some(table) => table,
+            None => return,
+        };
+        match operation {
+            AlterTableOperation::AddColumn { column_def, .. } => table.add_column(column_def),
+            AlterTableOperation::DropColumn { column_name, .. } => {
+                table.drop_column(&column_name.value)
+            }
+            AlterTableOperation::AlterColumn { column_name, op } => {
+                let column = match table.column_mut(&column_name.value) {
+                    Some(column) => column,
+                    None => return,
+                };
+                match op {
+                    AlterColumnOperation::SetNotNull => column.nullable = false,
+                    AlterColumnOperation::DropNotNull => column.nullable = true,
+                    AlterColumnOperation::SetDefault { value } => {
+                        column.default = Some(value.to_string())
+                    }
+                    AlterColumnOperation::DropDefault => column.default = None,
+                    AlterColumnOperation::SetDataType { data_type, .. } => {
+                        column.data_type = data_type.to_string()
+                    }
+                    _ => {}
+                }
+            }
+            // MySQL: MODIFY col TYPE ...
+            AlterTableOperation::ModifyColumn {
+                col_name,
+                data_type,
+                options,
+                ..
+            } => {
+                if let Some(c) = table.column_mut(&col_name.value) {
+                    *c = column(&col_name.value, data_type, options);
+                }
+            }
+            AlterTableOperation::AddConstraint(constraint) => table.add_constraint(constraint),
+            AlterTableOperation::DropPrimaryKey => table.primary_key.clear(),
+            _ => {}
+        }
+    }
+
+    // a column and the keys of every table referring to it
+    fn rename_column(&mut self, table_name: &str, from: &str, to: &str) {
+        let rename = |name: &mut String| {
+            if same_name(name, from) {
+                *name = to.to_string();
+            }
+        };
+        for table in &mut self.tables {
+            if same_name(&table.name, table_name) {
+                if let Some(column) = table.column_mut(from) {
+                    column.name = to.to_string();
+                }
+                table
+                    .primary_key
+                    .iter_mut()
+                    .chain(table.indexes.iter_mut().flat_map(|i| i.columns.iter_mut()))
+                    .chain(
+                        table
+                            .foreign_keys
+                            .iter_mut()
+                            .flat_map(|f| f.columns.iter_mut()),
+                    )
+                    .for_each(rename);
+            }
+            table
+                .foreign_keys
+                .iter_mut()
+                .filter(|f| same_name(&f.foreign_table, table_name))
+                .flat_map(|f| f.referred_columns.iter_mut())
+                .for_each(rename);
+        }
+    }
+
+    fn create_index(&mut self, create_index: &CreateIndex) {
+        let table = match self.table_mut(&object_name(&create_index.table_name)) {
+            Some(table) => table,
+            None => return,
+        };
+        table.indexes.push(Index {
+            name: create_index.name.as_ref().map(object_name),
+            columns: create_index
+                .columns
+                .iter()
+                .map(|c| match &c.expr {
+                    Expr::Identifier(ident) => ident.value.clone(),
+                    expr => expr.to_string(),
+                })
+                .collect(),
+            unique: create_index.unique,
+        });
+    }
+}
+
+impl Table {
+    fn add_column(&mut self, column_def: &ColumnDef) {
+        let options: Vec<ColumnOption> = column_def
+            .options
+            .iter()
+            .map(|o| o.option.clone())
+            .collect();
+        let column = column(&column_def.name.value, &column_def.data_type, &options);
+        for option in &options {
+            match option {
+                ColumnOption::Unique {
+                    is_primary: true, ..
+                } => {
+                    self.primary_key = vec![column.name.clone()];
+                }
+                ColumnOption::Unique {
+                    is_primary: false, ..
+                } => self.indexes.push(Index {
+                    name: None,
+                    columns: vec![column.name.clone()],
+                    unique: true,
+                }),
+                ColumnOption::ForeignKey {
+                    foreign_table,
+                    referred_columns,
+                    ..
+                } => self.foreign_keys.push(ForeignKey {
+                    columns: vec![column.name.clone()],
+                    foreign_table: object_name(foreign_table),
+                    referred_columns: idents(referred_columns),
+                }),
+                _ => {}
+            }
+        }
+        self.columns.push(column);
+    }
+
+    // the column goes out of the primary key and indexes, and its foreign keys are dropped
+    fn drop_column(&mut self, name: &str) {
+        self.columns.retain(|c| !same_name(&c.name, name));
+        self.primary_key.retain(|c| !same_name(c, name));
+        for index in &mut self.indexes {
+            index.columns.retain(|c| !same_name(c, name));
+        }
+        self.indexes.retain(|i| !i.columns.is_empty());
+        self.foreign_keys
+            .retain(|f| !f.columns.iter().any(|c| same_name(c, name)));
+    }
+
+    fn add_constraint(&mut self, constraint: &TableConstraint) {
+        match constraint {
+            TableConstraint::PrimaryKey { columns, .. } => {
+                self.primary_key = idents(columns);
+                for name in &self.primary_key {
+                    if let Some(column) = self.columns.iter_mut().find(|c| &c.name == name) {
+                        column.nullable = false;
+                    }
+                }
+            }
+            TableConstraint::Unique { name, columns, .. } => self.indexes.push(Index {
+                name: name.as_ref().map(|n| n.value.clone()),
+                columns: idents(columns),
+                unique: true,
+            }),
+            TableConstraint::ForeignKey {
+                columns,
+                foreign_table,
+                referred_columns,
+                ..
+            } => self.foreign_keys.push(ForeignKey {
+                columns: idents(columns),
+                foreign_table: object_name(foreign_table),
+                referred_columns: idents(referred_columns),
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn column(name: &str, data_type: &sqlparser::ast::DataType, options: &[ColumnOption]) -> Column {
+    let primary_key = options.iter().any(|o| {
+        matches!(
+            o,
+            ColumnOption::Unique {
+                is_primary: true,
+                ..
+            }
+        )
+    });
+    Column {
+        name: name.to_string(),
+        data_type: data_type.to_string(),
+        nullable: !primary_key && !options.iter().any(|o| matches!(o, ColumnOption::NotNull)),
+        default: options.iter().find_map(|o| match o {
+            ColumnOption::Default(expr) => Some(expr.to_string()),
+            _ => None,
+        }),
+    }
+}
+
+// ex) public.todos -> todos
+fn object_name(name: &ObjectName) -> String {
+    name.0.last().map(|i| i.value.clone()).unwrap_or_default()
+}
+
+fn idents(idents: &[sqlparser::ast::Ident]) -> Vec<String> {
+    idents.iter().map(|i| i.value.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn migration(path: &str, sql: &str) -> Migration {
+        Migration {
+            path: path.to_string(),
+            sql: sql.to_string(),
+        }
+    }
+
+    #[test]
+    fn replay_example_workspace_migrations() {
+        let dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example-workspaces/rs/migrations");
+        let (catalog, errors) = load_catalog(&dir, Dialect::PostgreSql).unwrap();
+        assert_eq!(errors, vec![]);
+        assert_eq!(
+            catalog,
+            Catalog {
+                tables: vec![Table {
+                    name: "todos".to_string(),
+                    columns: vec![
+                        Column {
+                            name: "id".to_string(),
+                            data_type: "BIGSERIAL".to_string(),
+                            nullable: false,
+                            default: None,
+                        },
+                        Column {
+                            name: "description".to_string(),
+                            data_type: "TEXT".to_string(),
+                            nullable: false,
+                            default: None,
+                        },
+                        Column {
+                            name: "done".to_string(),
+                            data_type: "BOOLEAN".to_string(),
+                            nullable: false,
+                            default: Some("false".to_string()),
+                        },
+                    ],
+                    primary_key: vec!["id".to_string()],
+                    ..Default::default()
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn replay_alter_table_and_indexes() {
+        let (catalog, errors) = Catalog::from_migrations(
+            &[
+                migration(
+                    "migrations/1_todos.sql",
+                    "CREATE TABLE todos (id BIGSERIAL PRIMARY KEY, description TEXT NOT NULL, done BOOLEAN NOT NULL)",
+                ),
+                migration(
+                    "migrations/2_users.sql",
+                    r#"
+CREATE TABLE users (id BIGINT PRIMARY KEY, name TEXT);
+CREATE UNIQUE INDEX users_name ON users (name);
+ALTER TABLE todos ADD COLUMN user_id BIGINT REFERENCES users (id);
+ALTER TABLE todos RENAME COLUMN description TO title;
+ALTER TABLE todos ALTER COLUMN done DROP NOT NULL;
+THIS IS NOT SQL;
+ALTER TABLE users RENAME TO accounts;
+"#,
+                ),
+            ],
+            Dialect::PostgreSql,
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path, "migrations/2_users.sql");
+
+        let todos = catalog.table("TODOS").unwrap();
+        assert_eq!(
+            todos
+                .columns
+                .iter()
+                .map(|c| (c.name.as_str(), c.nullable))
+                .collect::<Vec<_>>(),
+            vec![
+                ("id", false),
+                ("title", false),
+                ("done", true),
+                ("user_id", true)
+            ]
+        );
+        assert_eq!(
+            todos.foreign_keys,
+            vec![ForeignKey {
+                columns: vec!["user_id".to_string()],
+                foreign_table: "accounts".to_string(),
+                referred_columns: vec!["id".to_string()],
+            }]
+        );
+
+        assert!(catalog.table("users").is_none());
+        assert_eq!(
+            catalog.table("accounts").unwrap().indexes,
+            vec![Index {
+                name: Some("users_name".to_string()),
+                columns: vec!["name".to_string()],
+                unique: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn replay_rename_column_updates_keys() {
+        let (catalog, errors) = Catalog::from_migrations(
+            &[migration(
+                "migrations/1_init.sql",
+                r#"
+CREATE TABLE users (id BIGINT PRIMARY KEY, name TEXT);
+CREATE INDEX users_id_name ON users (id, name);
+CREATE TABLE todos (id BIGINT PRIMARY KEY, user_id BIGINT REFERENCES users (id));
+ALTER TABLE users RENAME COLUMN id TO user_id;
+ALTER TABLE todos RENAME COLUMN user_id TO owner_id;
+"#,
+            )],
+            Dialect::PostgreSql,
+        );
+        assert_eq!(errors, vec![]);
+
+        let users = catalog.table("users").unwrap();
+        assert_eq!(users.primary_key, vec!["user_id".to_string()]);
+        assert_eq!(
+            users.indexes[0].columns,
+            vec!["user_id".to_string(), "name".to_string()]
+        );
+        assert_eq!(
+            catalog.table("todos").unwrap().foreign_keys,
+            vec![ForeignKey {
+                columns: vec!["owner_id".to_string()],
+                foreign_table: "users".to_string(),
+                referred_columns: vec!["user_id".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn replay_drop_column_updates_keys() {
+        let (catalog, errors) = Catalog::from_migrations(
+            &[migration(
+                "migrations/1_init.sql",
+                r#"
+CREATE TABLE users (id BIGINT PRIMARY KEY);
+CREATE TABLE todos (
+    user_id BIGINT REFERENCES users (id),
+    position INT,
+    title TEXT,
+    PRIMARY KEY (user_id, position)
+);
+CREATE INDEX todos_position_title ON todos (position, title);
+CREATE INDEX todos_position ON todos (position);
+ALTER TABLE todos DROP COLUMN position;
+ALTER TABLE todos DROP COLUMN user_id;
+"#,
+            )],
+            Dialect::PostgreSql,
+        );
+        assert_eq!(errors, vec![]);
+
+        let todos = catalog.table("todos").unwrap();
+        assert_eq!(
+            todos,
+            &Table {
+                name: "todos".to_string(),
+                columns: vec![Column {
+                    name: "title".to_string(),
+                    data_type: "TEXT".to_string(),
+                    nullable: true,
+                    default: None,
+                }],
+                indexes: vec![Index {
+                    name: Some("todos_position_title".to_string()),
+                    columns: vec!["title".to_string()],
+                    unique: false,
+                }],
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn replay_change_column_keeps_position() {
+        let (catalog, errors) = Catalog::from_migrations(
+            &[migration(
+                "migrations/1_init.sql",
+                r#"
+CREATE TABLE users (id BIGINT PRIMARY KEY, name TEXT, email TEXT);
+CREATE UNIQUE INDEX users_name ON users (name);
+CREATE TABLE todos (id BIGINT PRIMARY KEY, user_id BIGINT, FOREIGN KEY (user_id) REFERENCES users (id));
+ALTER TABLE users CHANGE name login VARCHAR(64) NOT NULL;
+ALTER TABLE users CHANGE id user_id BIGINT;
+"#,
+            )],
+            Dialect::MySql,
+        );
+        assert_eq!(errors, vec![]);
+
+        let users = catalog.table("users").unwrap();
+        assert_eq!(
+            users
+                .columns
+                .iter()
+                .map(|c| (c.name.as_str(), c.data_type.as_str(), c.nullable))
+                .collect::<Vec<_>>(),
+            vec![
+                ("user_id", "BIGINT", false),
+                ("login", "VARCHAR(64)", false),
+                ("email", "TEXT", true)
+            ]
+        );
+        assert_eq!(users.primary_key, vec!["user_id".to_string()]);
+        assert_eq!(users.indexes[0].columns, vec!["login".to_string()]);
+        assert_eq!(
+            catalog.table("todos").unwrap().foreign_keys[0].referred_columns,
+            vec!["user_id".to_string()]
+        );
+    }
+
+    #[test]
+    fn sqlx_migration_file_order() {
+        assert_eq!(
+            migration_version("20200718111257_todos.sql"),
+            Some(20200718111257)
+        );
+        assert_eq!(migration_version("2_add_users.up.sql"), Some(2));
+        assert_eq!(migration_version("2_add_users.down.sql"), None);
+        assert_eq!(migration_version("README.md"), None);
+    }
+}
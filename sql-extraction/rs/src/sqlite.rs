@@ -0,0 +1,204 @@
+use crate::diagnostic::{Diagnostic, FileDiagnostic, Severity};
+use crate::dialect::Dialect;
+use crate::schema::migrations::{read_migrations, Migration};
+use crate::schema::SchemaError;
+use crate::workspace::rust_files;
+use crate::{extract_sql_nodes, Config, SqlNode};
+use rusqlite::Connection;
+use std::path::Path;
+
+/// An in-memory SQLite database with the migrations applied, to prepare queries against.
+pub struct SqliteValidator {
+    connection: Connection,
+}
+
+impl SqliteValidator {
+    /// Apply migrations in order. A migration that fails is reported and skipped.
+    pub fn from_migrations(
+        migrations: &[Migration],
+    ) -> rusqlite::Result<(SqliteValidator, Vec<SchemaError>)> {
+        let connection = Connection::open_in_memory()?;
+        let errors = migrations
+            .iter()
+            .filter_map(|migration| {
+                let err = connection.execute_batch(&migration.sql).err()?;
+                Some(SchemaError {
+                    path: migration.path.clone(),
+                    message: err.to_string(),
+                })
+            })
+            .collect();
+        Ok((SqliteValidator { connection }, errors))
+    }
+
+    /// Prepare the query of a node, reporting what SQLite rejects (unknown tables and
+    /// columns, ambiguous names, ...) in the Rust literal.
+    pub fn check(&self, sql_node: &SqlNode) -> Vec<Diagnostic> {
+        let sql = &sql_node.literal.value;
+        let err = match self.connection.prepare(sql) {
+            Ok(_) => return vec![],
+            Err(err) => err,
+        };
+        let (message, range) = match err {
+            // only the first statement is prepared, sqlx rejects the others anyway
+            rusqlite::Error::MultipleStatement => return vec![],
+            rusqlite::Error::SqlInputError { msg, offset, .. } if offset >= 0 => {
+                let start = sql
+                    .get(..offset as usize)
+                    .map(|s| s.chars().count())
+                    .unwrap_or_default();
+                let end = sql[sql.char_indices().nth(start).map_or(sql.len(), |(i, _)| i)..]
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .count()
+                    .max(1);
+                (msg, sql_node.literal.range_of(start, start + end))
+            }
+            rusqlite::Error::SqlInputError { msg, .. }
+            | rusqlite::Error::SqliteFailure(_, Some(msg)) => (msg, sql_node.literal.range),
+            err => (err.to_string(), sql_node.literal.range),
+        };
+        vec![Diagnostic {
+            range,
+            severity: Severity::Error,
+            code: "sqlite-error".to_string(),
+            message,
+        }]
+    }
+}
+
+/// Validate the SQLite queries of the Rust files under `root` against the migrations of
+/// `migrations_dir`. Failed migrations are reported first, with the path of the migration.
+pub fn check_workspace(
+    root: &Path,
+    migrations_dir: &Path,
+    configs: &[Config],
+) -> std::io::Result<Vec<FileDiagnostic>> {
+    let (validator, errors) = SqliteValidator::from_migrations(&read_migrations(migrations_dir)?)
+        .map_err(std::io::Error::other)?;
+    let mut diagnostics: Vec<FileDiagnostic> = errors
+        .into_iter()
+        .map(|err| FileDiagnostic {
+            path: err.path,
+            diagnostic: Diagnostic {
+                range: Default::default(),
+                severity: Severity::Error,
+                code: "sqlite-migration-error".to_string(),
+                message: err.message,
+            },
+        })
+        .collect();
+
+    for file in rust_files(root)? {
+        let sql_nodes =
+            match extract_sql_nodes(&std::fs::read_to_string(&file)?, configs, Dialect::Sqlite) {
+                Ok(sql_nodes) => sql_nodes,
+                Err(_) => continue,
+            };
+        for sql_node in sql_nodes.iter().filter(|n| n.dialect == Dialect::Sqlite) {
+            diagnostics.extend(validator.check(sql_node).into_iter().map(|diagnostic| {
+                FileDiagnostic {
+                    path: file.to_string_lossy().to_string(),
+                    diagnostic,
+                }
+            }));
+        }
+    }
+    Ok(diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::default_configs;
+    use crate::{Position, Range};
+    use pretty_assertions::assert_eq;
+
+    fn validator() -> SqliteValidator {
+        let (validator, errors) = SqliteValidator::from_migrations(&[
+            Migration {
+                path: "migrations/1_todos.sql".to_string(),
+                sql: "CREATE TABLE todos (id INTEGER PRIMARY KEY, description TEXT NOT NULL);"
+                    .to_string(),
+            },
+            Migration {
+                path: "migrations/2_users.sql".to_string(),
+                sql: "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT);".to_string(),
+            },
+        ])
+        .unwrap();
+        assert_eq!(errors, vec![]);
+        validator
+    }
+
+    fn check(source_txt: &str) -> Vec<Diagnostic> {
+        let validator = validator();
+        extract_sql_nodes(source_txt, &default_configs(), Dialect::Sqlite)
+            .unwrap()
+            .iter()
+            .flat_map(|sql_node| validator.check(sql_node))
+            .collect()
+    }
+
+    #[test]
+    fn valid_queries() {
+        assert_eq!(
+            check(
+                r#"
+async fn list(pool: &SqlitePool, id: i64) {
+    sqlx::query!("SELECT id, description FROM todos WHERE id = ?", id);
+    sqlx::query!("INSERT INTO users (name) VALUES ($1)", name);
+}
+"#
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn unknown_column_and_ambiguous_name() {
+        let result = check(
+            r#"
+async fn list(pool: &SqlitePool) {
+    sqlx::query!("SELECT done FROM todos");
+    sqlx::query!("SELECT id FROM todos, users");
+}
+"#,
+        );
+        assert_eq!(
+            result,
+            vec![
+                Diagnostic {
+                    range: Range {
+                        start: Position {
+                            line: 2,
+                            character: 25,
+                        },
+                        end: Position {
+                            line: 2,
+                            character: 29,
+                        },
+                    },
+                    severity: Severity::Error,
+                    code: "sqlite-error".to_string(),
+                    message: "no such column: done".to_string(),
+                },
+                Diagnostic {
+                    range: Range {
+                        start: Position {
+                            line: 3,
+                            character: 25,
+                        },
+                        end: Position {
+                            line: 3,
+                            character: 27,
+                        },
+                    },
+                    severity: Severity::Error,
+                    code: "sqlite-error".to_string(),
+                    message: "ambiguous column name: id".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::dialect::Dialect;
+use crate::literal::offset_of_location;
+use crate::{Position, Range, SqlNode};
+use sqlparser::ast::Statement;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan, Tokenizer};
+
+/// Parse the SQL of a node with its dialect and report syntax errors located in the Rust literal.
+pub fn check_syntax(sql_node: &SqlNode) -> Vec<Diagnostic> {
+    let sql = &sql_node.literal.value;
+    let parser_dialect = sql_node.dialect.parser_dialect();
+
+    let tokens = match Tokenizer::new(&*parser_dialect, sql).tokenize_with_location() {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            let offset = offset_of_location(sql, err.location.line, err.location.column);
+            return vec![syntax_error(sql_node, &err.message, offset, offset + 1)];
+        }
+    };
+
+    let err = match Parser::new(&*parser_dialect)
+        .with_tokens_with_locations(tokens.clone())
+        .parse_statements()
+    {
+        Ok(_) => return vec![],
+        Err(err) => err,
+    };
+    let message = match err {
+        ParserError::TokenizerError(message) | ParserError::ParserError(message) => message,
+        ParserError::RecursionLimitExceeded => "recursion limit exceeded".to_string(),
+    };
+
+    // sqlparser appends " at Line: X, Column: Y" to the message
+    let (message, location) = match message.rsplit_once(" at Line: ") {
+        Some((message, location)) => (message.to_string(), parse_location(location)),
+        None => (message, None),
+    };
+    let (start, end) = match location.and_then(|(line, column)| token_at(&tokens, line, column)) {
+        Some(token) => (
+            offset_of_location(sql, token.span.start.line, token.span.start.column),
+            offset_of_location(sql, token.span.end.line, token.span.end.column),
+        ),
+        // unexpected end of input: point at the end of the query
+        None => {
+            let end = sql.trim_end().chars().count();
+            (end.saturating_sub(1), end)
+        }
+    };
+
+    vec![syntax_error(sql_node, &message, start, end)]
+}
+
+fn syntax_error(sql_node: &SqlNode, message: &str, start: usize, end: usize) -> Diagnostic {
+    Diagnostic {
+        range: sql_node.literal.range_of(start, end),
+        severity: Severity::Error,
+        code: "syntax-error".to_string(),
+        message: message.to_string(),
+    }
+}
+
+// "1, Column: 8" -> (1, 8)
+fn parse_location(location: &str) -> Option<(u64, u64)> {
+    let (line, column) = location.split_once(", Column: ")?;
+    Some((line.trim().parse().ok()?, column.trim().parse().ok()?))
+}
+
+fn token_at(tokens: &[TokenWithSpan], line: u64, column: u64) -> Option<&TokenWithSpan> {
+    tokens.iter().find(|t| {
+        t.token != Token::EOF && t.span.start.line == line && t.span.start.column == column
+    })
+}
+
+/// One statement of a SQL script, parsed on its own so that an error does not hide the others.
+pub(crate) struct ParsedStatement {
+    pub statement: Result<Statement, String>,
+    /// position in the script, `;` excluded
+    pub range: Range,
+}
+
+/// Split a script at `;` tokens and parse each statement.
+pub(crate) fn parse_each_statement(sql: &str, dialect: Dialect) -> Vec<ParsedStatement> {
+    let parser_dialect = dialect.parser_dialect();
+    let tokens = match Tokenizer::new(&*parser_dialect, sql).tokenize_with_location() {
+        Ok(tokens) => tokens,
+        Err(err) => {
+            return vec![ParsedStatement {
+                statement: Err(err.to_string()),
+                range: Range::default(),
+            }]
+        }
+    };
+
+    tokens
+        .split(|t| t.token == Token::SemiColon)
+        .filter_map(|chunk| {
+            // skip empty statements, ex) after the last `;`
+            let mut tokens = chunk
+                .iter()
+                .filter(|t| !matches!(t.token, Token::Whitespace(_) | Token::EOF));
+            let first = tokens.next()?;
+            let last = tokens.next_back().unwrap_or(first);
+            let range = Range {
+                start: script_position(first.span.start),
+                end: script_position(last.span.end),
+            };
+            let statement = Parser::new(&*parser_dialect)
+                .with_tokens_with_locations(chunk.to_vec())
+                .parse_statement()
+                .map_err(|err| err.to_string());
+            Some(ParsedStatement { statement, range })
+        })
+        .collect()
+}
+
+// sqlparser locations are 1-indexed
+fn script_position(location: Location) -> Position {
+    Position {
+        line: (location.line as usize).saturating_sub(1),
+        character: (location.column as usize).saturating_sub(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{default_configs, extract_sql_nodes};
+    use pretty_assertions::assert_eq;
+
+    fn check(source_txt: &str, dialect: Dialect) -> Vec<Diagnostic> {
+        extract_sql_nodes(source_txt, &default_configs(), dialect)
+            .unwrap()
+            .iter()
+            .flat_map(check_syntax)
+            .collect()
+    }
+
+    #[test]
+    fn no_error_for_valid_queries() {
+        let result = check(
+            r##"
+async fn complete_todo(pool: &PgPool, id: i64) -> anyhow::Result<bool> {
+    let rec = sqlx::query!("SELECT id \"id\", description FROM todos WHERE id = $1", id);
+    let rows_affected = sqlx::query!(
+        r#"
+UPDATE todos
+SET done = TRUE
+WHERE id = $1
+        "#,
+        id
+    );
+}
+            "##,
+            Dialect::PostgreSql,
+        );
+        assert_eq!(result, vec![]);
+    }
+
+    #[test]
+    fn syntax_error_mapped_into_multi_line_literal() {
+        let result = check(
+            r##"
+async fn list_todos(pool: &PgPool) -> anyhow::Result<()> {
+    let recs = sqlx::query!(
+        r#"
+SELECT id, description
+FORM todos
+        "#
+    );
+}
+            "##,
+            Dialect::PostgreSql,
+        );
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].range,
+            Range {
+                start: Position {
+                    line: 5,
+                    character: 5,
+                },
+                end: Position {
+                    line: 5,
+                    character: 10,
+                },
+            }
+        );
+        assert_eq!(result[0].code, "syntax-error");
+        assert!(result[0].message.contains("found: todos"));
+    }
+
+    #[test]
+    fn syntax_error_mapped_through_escapes() {
+        // `\"` takes two chars in the source but one in the SQL
+        let result = check(
+            r#"
+fn main() {
+    sqlx::query!("SELECT \"id\" FROM todos WHERE WHERE id = 1");
+}
+            "#,
+            Dialect::Sqlite,
+        );
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].range,
+            Range {
+                start: Position {
+                    line: 2,
+                    character: 55,
+                },
+                end: Position {
+                    line: 2,
+                    character: 57,
+                },
+            }
+        );
+        assert!(result[0].message.contains("found: id"));
+    }
+
+    #[test]
+    fn syntax_error_at_end_of_query() {
+        let result = check(
+            r#"
+fn main() {
+    sqlx::query!("SELECT id FROM");
+}
+            "#,
+            Dialect::MySql,
+        );
+        assert_eq!(result.len(), 1);
+        assert_eq!(
+            result[0].range,
+            Range {
+                start: Position {
+                    line: 2,
+                    character: 31,
+                },
+                end: Position {
+                    line: 2,
+                    character: 32,
+                },
+            }
+        );
+    }
+}
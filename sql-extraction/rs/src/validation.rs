@@ -0,0 +1,185 @@
+use crate::dialect::Dialect;
+use crate::project::ProjectConfig;
+use crate::Config;
+use serde::Serialize;
+use serde_json::Value;
+use std::fmt;
+
+/// A config entry that cannot be used, ex) `configs[1].sqlArgno: unknown field, did you mean
+/// sqlArgNo?`.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// index of the entry
+    pub entry: usize,
+    /// `None` when the entry itself is wrong, ex) not a JSON object
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(f, "configs[{}].{}: {}", self.entry, field, self.message),
+            None => write!(f, "configs[{}]: {}", self.entry, self.message),
+        }
+    }
+}
+
+// fields of `Config`: name, expected type, required
+const FIELDS: [(&str, &str, bool); 4] = [
+    ("functionName", "a string", true),
+    ("sqlArgNo", "a non-negative integer", true),
+    ("isMacro", "a boolean", true),
+    (
+        "dialect",
+        "one of \"generic\", \"postgresql\", \"mysql\", \"sqlite\"",
+        false,
+    ),
+];
+
+/// Parse JSON config entries one by one, keeping the valid ones. Every problem of an invalid
+/// entry is reported, not only the first one.
+pub fn validate_configs(configs: &[String]) -> (Vec<Config>, Vec<ConfigError>) {
+    let mut valid = Vec::new();
+    let mut errors = Vec::new();
+    for (entry, config) in configs.iter().enumerate() {
+        let entry_errors = match serde_json::from_str::<Value>(config) {
+            Ok(value) => match validate_config(&value) {
+                Ok(config) => {
+                    valid.push(config);
+                    continue;
+                }
+                Err(entry_errors) => entry_errors,
+            },
+            Err(err) => vec![(None, format!("invalid JSON, {}", err))],
+        };
+        errors.extend(
+            entry_errors
+                .into_iter()
+                .map(|(field, message)| ConfigError {
+                    entry,
+                    field,
+                    message,
+                }),
+        );
+    }
+    (valid, errors)
+}
+
+type FieldError = (Option<String>, String);
+
+fn validate_config(value: &Value) -> Result<Config, Vec<FieldError>> {
+    let object = match value {
+        Value::Object(object) => object,
+        value => {
+            return Err(vec![(
+                None,
+                format!("expected an object, found {}", describe(value)),
+            )])
+        }
+    };
+    let mut errors: Vec<FieldError> = FIELDS
+        .iter()
+        .filter(|(name, _, required)| *required && !object.contains_key(*name))
+        .map(|(name, expected, _)| {
+            (
+                Some(name.to_string()),
+                format!("missing field, expected {}", expected),
+            )
+        })
+        .collect();
+    for (name, value) in object {
+        let message = match FIELDS.iter().find(|(field, ..)| field == name) {
+            Some((_, expected, _)) if !has_type(name, value) => {
+                format!("expected {}, found {}", expected, describe(value))
+            }
+            Some(_) => continue,
+            None => match FIELDS
+                .iter()
+                .find(|(field, ..)| field.eq_ignore_ascii_case(name))
+            {
+                Some((field, ..)) => format!("unknown field, did you mean {}?", field),
+                None => format!(
+                    "unknown field, expected one of {}",
+                    FIELDS.map(|(field, ..)| field).join(", ")
+                ),
+            },
+        };
+        errors.push((Some(name.clone()), message));
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    serde_json::from_value(value.clone()).map_err(|err| vec![(None, err.to_string())])
+}
+
+fn has_type(field: &str, value: &Value) -> bool {
+    match field {
+        "functionName" => value.is_string(),
+        "sqlArgNo" => value.is_u64(),
+        "isMacro" => value.is_boolean(),
+        "dialect" => serde_json::from_value::<Dialect>(value.clone()).is_ok(),
+        _ => false,
+    }
+}
+
+// ex) `string "0"`, `number -1`
+fn describe(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("boolean {}", b),
+        Value::Number(n) => format!("number {}", n),
+        Value::String(s) => format!("string {:?}", s),
+        Value::Array(_) => "an array".to_string(),
+        Value::Object(_) => "an object".to_string(),
+    }
+}
+
+/// JSON schema of `sqlsurge.toml` and of the `sqlsurge` metadata of `Cargo.toml`, the
+/// definition of `Config` also applies to `sqlsurge.customRawSqlQuery` entries.
+pub fn config_schema() -> String {
+    serde_json::to_string_pretty(&schemars::schema_for!(ProjectConfig)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn keep_valid_entries() {
+        let (valid, errors) = validate_configs(&[
+            r#"{ "functionName": "query", "sqlArgNo": 0, "isMacro": true }"#.to_string(),
+            r#"{ "functionName": "fetch", "sqlArgno": 1, "isMacro": "false" }"#.to_string(),
+            r#"{ "functionName": "sql_query", "sqlArgNo": -1, "isMacro": false, "dialect": "oracle", "lang": "rust" }"#
+                .to_string(),
+            "[]".to_string(),
+        ]);
+        assert_eq!(
+            valid
+                .iter()
+                .map(|c| c.functionName.as_str())
+                .collect::<Vec<_>>(),
+            vec!["query"]
+        );
+        assert_eq!(
+            errors.iter().map(ConfigError::to_string).collect::<Vec<_>>(),
+            vec![
+                "configs[1].sqlArgNo: missing field, expected a non-negative integer",
+                "configs[1].isMacro: expected a boolean, found string \"false\"",
+                "configs[1].sqlArgno: unknown field, did you mean sqlArgNo?",
+                "configs[2].dialect: expected one of \"generic\", \"postgresql\", \"mysql\", \"sqlite\", found string \"oracle\"",
+                "configs[2].lang: unknown field, expected one of functionName, sqlArgNo, isMacro, dialect",
+                "configs[2].sqlArgNo: expected a non-negative integer, found number -1",
+                "configs[3]: expected an object, found an array",
+            ]
+        );
+    }
+
+    #[test]
+    fn schema_is_up_to_date() {
+        // UPDATE_EXPECT=1 cargo test to regenerate
+        expect_test::expect_file!["../sqlsurge.schema.json"].assert_eq(&config_schema());
+    }
+}
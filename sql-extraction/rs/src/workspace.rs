@@ -0,0 +1,72 @@
+use crate::Range;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// build output and dependencies never hold the workspace's queries
+const SKIPPED_DIRS: [&str; 4] = ["target", "node_modules", ".git", ".sqlx"];
+
+/// Rust source files under `root`, sorted for stable output.
+pub fn rust_files(root: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect_files(root, "rs", &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// A range in a file of the workspace.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Location {
+    pub path: String,
+    pub range: Range,
+}
+
+/// A file of the workspace with its content, for callers without file system access.
+#[derive(Deserialize, Debug, Clone)]
+pub struct SourceFile {
+    pub path: String,
+    pub content: String,
+}
+
+/// Rust source files under `root` with their content.
+pub fn read_rust_files(root: &Path) -> std::io::Result<Vec<SourceFile>> {
+    rust_files(root)?
+        .into_iter()
+        .map(|path| {
+            Ok(SourceFile {
+                content: std::fs::read_to_string(&path)?,
+                path: path.to_string_lossy().to_string(),
+            })
+        })
+        .collect()
+}
+
+fn collect_files(dir: &Path, extension: &str, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if !SKIPPED_DIRS.contains(&file_name.as_ref()) {
+                collect_files(&path, extension, files)?;
+            }
+        } else if path.extension().is_some_and(|e| e == extension) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_rust_files_of_example_workspace() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../example-workspaces/rs");
+        let files: Vec<String> = rust_files(&root)
+            .unwrap()
+            .iter()
+            .map(|f| f.strip_prefix(&root).unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(files, vec!["src/diesel.rs", "src/main.rs"]);
+    }
+}
@@ -11,6 +11,7 @@ export type SqlNode = {
   };
   content: string;
   method_line: number; // 0-based
+  dialect?: "generic" | "postgresql" | "mysql" | "sqlite"; // Rust only
 };
 
 export const ORIGINAL_SCHEME = "sqlsurge";